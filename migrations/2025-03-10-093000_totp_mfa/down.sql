@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE mfa_challenges;
+DROP TABLE totp_secrets;
+ALTER TABLE users DROP COLUMN mfa_type;
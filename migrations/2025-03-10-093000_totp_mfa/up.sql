@@ -0,0 +1,26 @@
+-- Your SQL goes here
+ALTER TABLE users ADD COLUMN mfa_type VARCHAR(20);
+-- NULL when MFA is disabled, otherwise one of 'authenticator', 'sms', 'email'
+
+CREATE TABLE totp_secrets (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret VARCHAR(64) NOT NULL,
+    -- Base32 encoded shared secret
+    confirmed_at TIMESTAMP,
+    -- Set once the user has proven possession with a first valid code
+    last_used_step BIGINT,
+    -- Time step of the last accepted code, so a code cannot be replayed
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE mfa_challenges (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    attempts SMALLINT NOT NULL DEFAULT 0,
+    expires_at TIMESTAMP NOT NULL,
+    consumed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_mfa_challenges_user_id ON mfa_challenges(user_id);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE webauthn_challenges;
+DROP TABLE webauthn_credentials;
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+CREATE TABLE webauthn_credentials (
+    id SERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    credential_id TEXT NOT NULL UNIQUE,
+    -- Base64url credential id as reported by the authenticator
+    public_key BYTEA NOT NULL,
+    -- Uncompressed SEC1 P-256 point taken from the COSE key
+    sign_count BIGINT NOT NULL DEFAULT 0,
+    name VARCHAR(100),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP
+);
+
+CREATE INDEX idx_webauthn_credentials_user_id ON webauthn_credentials(user_id);
+
+CREATE TABLE webauthn_challenges (
+    id SERIAL PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- NULL for discoverable credential logins where the user is not known up front
+    challenge VARCHAR(64) NOT NULL UNIQUE,
+    ceremony VARCHAR(20) NOT NULL,
+    -- 'registration' or 'authentication'
+    expires_at TIMESTAMP NOT NULL,
+    consumed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
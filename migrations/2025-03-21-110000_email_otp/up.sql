@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE mfa_challenges ADD COLUMN code_hash VARCHAR(64);
+-- SHA-256 hex digest of the one-time code emailed for 'email' MFA challenges
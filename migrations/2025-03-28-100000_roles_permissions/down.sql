@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE user_roles;
+DROP TABLE role_permissions;
+DROP TABLE permissions;
+DROP TABLE roles;
@@ -0,0 +1,51 @@
+-- Your SQL goes here
+CREATE TABLE roles (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) UNIQUE NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE permissions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    -- Namespaced as resource:action, e.g. users:read
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE role_permissions (
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id INTEGER NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+-- Additional roles on top of the primary role in users.role
+CREATE TABLE user_roles (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, role_id)
+);
+
+CREATE INDEX idx_user_roles_role_id ON user_roles(role_id);
+
+INSERT INTO roles (name, description) VALUES
+    ('user', 'Regular account'),
+    ('admin', 'Full administrative access'),
+    ('moderator', 'Can review accounts and end sessions'),
+    ('support', 'Read-only access to accounts');
+
+INSERT INTO permissions (name, description) VALUES
+    ('users:read', 'View any user account'),
+    ('users:write', 'Create and update any user account'),
+    ('users:delete', 'Delete any user account'),
+    ('roles:assign', 'Change the roles of a user'),
+    ('sessions:read', 'View sessions of any user'),
+    ('sessions:revoke', 'End sessions of any user');
+
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT r.id, p.id FROM roles r, permissions p
+WHERE r.name = 'admin'
+   OR (r.name = 'moderator' AND p.name IN ('users:read', 'sessions:read', 'sessions:revoke'))
+   OR (r.name = 'support' AND p.name IN ('users:read', 'sessions:read'));
@@ -0,0 +1,32 @@
+-- Your SQL goes here
+CREATE TABLE organizations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(50) UNIQUE NOT NULL,
+    -- URL-friendly identifier, lowercase letters, digits and dashes
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT slug_format CHECK (slug ~ '^[a-z0-9-]+$')
+);
+
+CREATE TABLE organization_members (
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL DEFAULT 'member',
+    -- Role within this organization: owner, admin or member
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX idx_organization_members_user_id ON organization_members(user_id);
+
+-- Organization the session is currently acting in
+ALTER TABLE sessions ADD COLUMN organization_id UUID REFERENCES organizations(id) ON DELETE SET NULL;
+
+-- Existing accounts all move into a default organization
+INSERT INTO organizations (name, slug) VALUES ('Default', 'default');
+
+INSERT INTO organization_members (organization_id, user_id, role)
+SELECT o.id, u.id, CASE WHEN u.role = 'admin' THEN 'admin' ELSE 'member' END
+FROM organizations o, users u
+WHERE o.slug = 'default';
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE organization_invitations (
+    id SERIAL PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    email CITEXT NOT NULL,
+    role VARCHAR(20) NOT NULL DEFAULT 'member',
+    -- Organization role the invitee gets on acceptance
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    expires_at TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_organization_invitations_organization_id ON organization_invitations(organization_id);
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE jwt_keys (
+    id SERIAL PRIMARY KEY,
+    kid VARCHAR(100) NOT NULL UNIQUE,
+    purpose VARCHAR(20) NOT NULL,
+    -- access or refresh
+    algorithm VARCHAR(10) NOT NULL,
+    private_key TEXT NOT NULL,
+    -- PKCS#8 PEM for asymmetric keys, the shared secret for HS256
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    -- pending: published for verification, not yet signing
+    -- active: the one key of its purpose that signs new tokens
+    -- retiring: still verifies tokens it signed until retire_after
+    -- retired: no longer used
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    activated_at TIMESTAMP,
+    retire_after TIMESTAMP,
+    retired_at TIMESTAMP
+);
+
+-- At most one signing key per purpose
+CREATE UNIQUE INDEX idx_jwt_keys_active_purpose ON jwt_keys(purpose) WHERE status = 'active';
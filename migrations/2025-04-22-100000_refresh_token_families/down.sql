@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE security_events;
+DROP TABLE refresh_tokens;
+ALTER TABLE sessions DROP COLUMN family_id;
@@ -0,0 +1,37 @@
+-- Your SQL goes here
+-- Every login starts a family; each refresh rotates within it
+ALTER TABLE sessions ADD COLUMN family_id UUID NOT NULL DEFAULT uuid_generate_v4();
+
+CREATE INDEX idx_sessions_family_id ON sessions(family_id);
+
+-- Every refresh token issued, so a replayed one can be recognized
+CREATE TABLE refresh_tokens (
+    id SERIAL PRIMARY KEY,
+    family_id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    -- SHA-256 hex digest of the token
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    rotated_at TIMESTAMP,
+    -- Set once the token has been exchanged for a new one
+    revoked_at TIMESTAMP
+    -- Set when the family is revoked
+);
+
+CREATE INDEX idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+
+CREATE TABLE security_events (
+    id SERIAL PRIMARY KEY,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    details TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_security_events_user_id ON security_events(user_id);
+
+-- Existing sessions become single-token families
+INSERT INTO refresh_tokens (family_id, user_id, token_hash)
+SELECT family_id, user_id, encode(sha256(convert_to(refresh_token, 'UTF8')), 'hex')
+FROM sessions
+ON CONFLICT (token_hash) DO NOTHING;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_sessions_refresh_token;
+DROP INDEX idx_sessions_token;
+-- Digests cannot be turned back into tokens, so everyone has to log in again
+DELETE FROM sessions;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+-- Sessions keep SHA-256 hex digests of their tokens instead of the tokens themselves
+UPDATE sessions SET
+    token = encode(sha256(convert_to(token, 'UTF8')), 'hex'),
+    refresh_token = encode(sha256(convert_to(refresh_token, 'UTF8')), 'hex');
+
+CREATE INDEX idx_sessions_token ON sessions(token);
+CREATE INDEX idx_sessions_refresh_token ON sessions(refresh_token);
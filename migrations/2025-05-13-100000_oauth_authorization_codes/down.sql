@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE sessions DROP COLUMN scope;
+ALTER TABLE sessions DROP COLUMN client_id;
+DROP TABLE oauth_authorization_codes;
+ALTER TABLE oauth_clients DROP COLUMN allowed_scopes;
+ALTER TABLE oauth_clients DROP COLUMN redirect_uris;
+DELETE FROM oauth_clients WHERE client_secret_hash IS NULL;
+ALTER TABLE oauth_clients ALTER COLUMN client_secret_hash SET NOT NULL;
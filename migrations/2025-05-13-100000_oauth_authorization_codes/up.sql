@@ -0,0 +1,26 @@
+-- Your SQL goes here
+-- Public clients (SPAs, native apps) have no secret and rely on PKCE
+ALTER TABLE oauth_clients ALTER COLUMN client_secret_hash DROP NOT NULL;
+ALTER TABLE oauth_clients ADD COLUMN redirect_uris TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE oauth_clients ADD COLUMN allowed_scopes TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE TABLE oauth_authorization_codes (
+    id SERIAL PRIMARY KEY,
+    code_hash VARCHAR(64) UNIQUE NOT NULL,
+    -- SHA-256 hex digest of the code
+    client_id VARCHAR(64) NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    redirect_uri TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    code_challenge VARCHAR(128) NOT NULL,
+    -- PKCE S256 challenge
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    family_id UUID,
+    -- Refresh token family issued for the code, revoked if the code is replayed
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Sessions started through OAuth are limited to the client's granted scope
+ALTER TABLE sessions ADD COLUMN client_id VARCHAR(64) REFERENCES oauth_clients(client_id) ON DELETE CASCADE;
+ALTER TABLE sessions ADD COLUMN scope TEXT;
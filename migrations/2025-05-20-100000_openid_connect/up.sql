@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- Carried from the authorization request into the ID token
+ALTER TABLE oauth_authorization_codes ADD COLUMN nonce TEXT;
+ALTER TABLE oauth_authorization_codes ADD COLUMN auth_time TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Access tokens issued to clients acting on their own behalf (client_credentials)
+CREATE TABLE oauth_client_tokens (
+    id SERIAL PRIMARY KEY,
+    client_id VARCHAR(64) NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) UNIQUE NOT NULL,
+    -- SHA-256 hex digest of the token
+    scope TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_oauth_client_tokens_client_id ON oauth_client_tokens(client_id);
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+-- Pending and approved device authorization requests (RFC 8628)
+CREATE TABLE oauth_device_codes (
+    id SERIAL PRIMARY KEY,
+    device_code_hash VARCHAR(64) UNIQUE NOT NULL,
+    -- SHA-256 hex digest of the device code
+    user_code VARCHAR(9) UNIQUE NOT NULL,
+    -- Short code the user types in, e.g. BCDF-GHJK
+    client_id VARCHAR(64) NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+    scope TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- Set once a user approves the request
+    poll_interval INTEGER NOT NULL DEFAULT 5,
+    -- Seconds the device has to wait between polls
+    last_polled_at TIMESTAMP,
+    approved_at TIMESTAMP,
+    denied_at TIMESTAMP,
+    auth_time TIMESTAMP,
+    redeemed_at TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_oauth_device_codes_expires_at ON oauth_device_codes(expires_at);
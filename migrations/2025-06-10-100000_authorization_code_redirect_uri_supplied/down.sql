@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE oauth_authorization_codes DROP COLUMN redirect_uri_supplied;
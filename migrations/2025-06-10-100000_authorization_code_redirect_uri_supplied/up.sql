@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- Whether the authorization request named its redirect_uri; only then does
+-- the token request have to repeat it (RFC 6749 section 4.1.3)
+ALTER TABLE oauth_authorization_codes ADD COLUMN redirect_uri_supplied BOOLEAN NOT NULL DEFAULT TRUE;
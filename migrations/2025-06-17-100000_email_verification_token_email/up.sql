@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- Address the token was sent to; it verifies nothing once the account's email changes
+ALTER TABLE email_verification_tokens ADD COLUMN email CITEXT;
+
+UPDATE email_verification_tokens t SET email = u.email FROM users u WHERE u.id = t.user_id;
+
+ALTER TABLE email_verification_tokens ALTER COLUMN email SET NOT NULL;
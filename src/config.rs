@@ -36,4 +36,37 @@ pub fn get_rust_log() -> Option<String> {
 
 pub fn get_max_db_connections() -> Option<u32> {
     env::var("MAX_DB_CONNECTIONS").ok().map(|s| s.parse().expect("MAX_DB_CONNECTIONS must be a number"))
-}
\ No newline at end of file
+}
+
+/// `iss` claim of issued tokens. Defaults to `PUBLIC_URL`, as OpenID Connect
+/// requires the issuer to be the URL the service is reached at.
+pub fn get_jwt_issuer() -> String {
+    env::var("JWT_ISSUER").unwrap_or_else(|_| get_public_url())
+}
+
+/// `aud` claim of access tokens; services accepting them should check it.
+pub fn get_jwt_audience() -> String {
+    env::var("JWT_AUDIENCE").unwrap_or_else(|_| "rusted-lock-api".to_string())
+}
+
+/// Base URL the service is reached at, used in the OpenID Connect discovery document.
+pub fn get_public_url() -> String {
+    env::var("PUBLIC_URL").unwrap_or_else(|_| format!("http://{}:{}", get_host(), get_port()))
+}
+
+/// Hex-encoded 32-byte key the signing keys in `jwt_keys` are encrypted with.
+pub fn get_key_encryption_key() -> Option<String> {
+    env::var("JWT_KEY_ENCRYPTION_KEY").ok()
+}
+
+/// Days after which the signing keys are rotated automatically. Unset disables the schedule.
+pub fn get_key_rotation_days() -> Option<i64> {
+    env::var("JWT_KEY_ROTATION_DAYS").ok().map(|s| s.parse().expect("JWT_KEY_ROTATION_DAYS must be a number"))
+}
+
+/// How logins from accounts with an unverified email are handled: `deny` (default) or `allow`.
+pub fn allow_unverified_login() -> bool {
+    env::var("UNVERIFIED_LOGIN_POLICY")
+        .map(|policy| policy.eq_ignore_ascii_case("allow"))
+        .unwrap_or(false)
+}
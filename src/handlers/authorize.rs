@@ -0,0 +1,369 @@
+// src/handlers/authorize.rs
+
+use axum::{
+    extract::{Form, Query, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_extra::TypedHeader;
+use axum_extra::headers::Cookie;
+use serde::{Deserialize, Serialize};
+use bcrypt::verify;
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use url::Url;
+use crate::config::allow_unverified_login;
+use crate::db::PgPool;
+use crate::handlers::login::{find_user, is_account_locked};
+use crate::models::{OAuthClient, Session, User};
+use crate::schema::{sessions, users};
+use crate::utils::account_status::can_sign_in;
+use crate::utils::authorization_code::{create_authorization_code, Approval};
+use crate::utils::id_token::{has_scope, oidc_enabled};
+use crate::utils::jwt::update_login_attempts;
+use crate::utils::jwt_validator::validate_refresh_token;
+use crate::utils::oauth_client::{allows_redirect_uri, find_client, grant_scope};
+use crate::utils::secure_token::{generate_token, hash_token};
+
+const CSRF_COOKIE: &str = "oauth_csrf";
+
+/// Authorization request parameters (RFC 6749 section 4.1.1, RFC 7636).
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AuthorizeParams {
+    response_type: Option<String>,
+    client_id: Option<String>,
+    redirect_uri: Option<String>,
+    scope: Option<String>,
+    state: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<String>,
+    // OpenID Connect
+    nonce: Option<String>,
+}
+
+/// The login/consent form posts the request parameters back along with the decision.
+#[derive(Deserialize, Debug)]
+pub struct AuthorizeForm {
+    #[serde(flatten)]
+    params: AuthorizeParams,
+    csrf_token: String,
+    username: Option<String>,
+    password: Option<String>,
+    decision: String,
+}
+
+/// An authorization request that passed validation.
+struct ValidRequest {
+    client: OAuthClient,
+    redirect_uri: String,
+    scope: String,
+    state: Option<String>,
+    code_challenge: String,
+}
+
+/// Why an authorization request was refused.
+enum Rejection {
+    /// The client or redirect URI cannot be trusted, so the user is not sent anywhere
+    Page(&'static str),
+    /// Reported back to the client's redirect URI
+    Redirect {
+        redirect_uri: String,
+        state: Option<String>,
+        error: &'static str,
+        description: &'static str,
+    },
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        match self {
+            Rejection::Page(message) => error_page(message),
+            Rejection::Redirect { redirect_uri, state, error, description } => redirect_to_client(
+                &redirect_uri,
+                &[("error", error), ("error_description", description)],
+                state.as_deref(),
+            ),
+        }
+    }
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub(crate) fn error_page(message: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Html(format!(
+            "<!DOCTYPE html><html><head><title>Authorization error</title></head>\
+             <body><h1>Authorization error</h1><p>{}</p></body></html>",
+            escape_html(message)
+        )),
+    ).into_response()
+}
+
+/// Sends the user back to the client with `params` and the request's `state`.
+fn redirect_to_client(redirect_uri: &str, params: &[(&str, &str)], state: Option<&str>) -> Response {
+    let mut url = match Url::parse(redirect_uri) {
+        Ok(url) => url,
+        Err(_) => return error_page("Invalid redirect URI"),
+    };
+    {
+        let mut query = url.query_pairs_mut();
+        for (key, value) in params {
+            query.append_pair(key, value);
+        }
+        if let Some(state) = state {
+            query.append_pair("state", state);
+        }
+    }
+    Redirect::to(url.as_str()).into_response()
+}
+
+fn redirect_error(request: &ValidRequest, error: &str, description: &str) -> Response {
+    redirect_to_client(
+        &request.redirect_uri,
+        &[("error", error), ("error_description", description)],
+        request.state.as_deref(),
+    )
+}
+
+/// Validates the request. Problems with the client or redirect URI are shown
+/// as an error page; everything else is reported back to the client.
+fn validate_request(conn: &mut PgConnection, params: &AuthorizeParams) -> Result<ValidRequest, Rejection> {
+    let client = match params.client_id.as_deref() {
+        Some(id) => find_client(conn, id).map_err(|_| Rejection::Page("Database error"))?,
+        None => None,
+    }
+    .ok_or(Rejection::Page("Unknown client"))?;
+
+    // Without a redirect_uri the client's only registered one is used
+    let redirect_uri = match params.redirect_uri.as_deref() {
+        Some(uri) if allows_redirect_uri(&client, uri) => uri.to_string(),
+        None if client.redirect_uris.len() == 1 => client.redirect_uris[0].clone(),
+        _ => return Err(Rejection::Page("Invalid redirect URI")),
+    };
+    let reject = |error, description| Rejection::Redirect {
+        redirect_uri: redirect_uri.clone(),
+        state: params.state.clone(),
+        error,
+        description,
+    };
+
+    if params.response_type.as_deref() != Some("code") {
+        return Err(reject("unsupported_response_type", "Only the authorization code flow is supported"));
+    }
+    let code_challenge = match (params.code_challenge.as_deref(), params.code_challenge_method.as_deref()) {
+        (Some(challenge), Some("S256")) if (43..=128).contains(&challenge.len()) => challenge.to_string(),
+        _ => return Err(reject("invalid_request", "PKCE with the S256 method is required")),
+    };
+    let scope = grant_scope(&client, params.scope.as_deref())
+        .ok_or_else(|| reject("invalid_scope", "The requested scope is not allowed for this client"))?;
+    if has_scope(Some(&scope), "openid") && !oidc_enabled() {
+        return Err(reject("invalid_scope", "OpenID Connect is not enabled on this server"));
+    }
+
+    Ok(ValidRequest {
+        client,
+        redirect_uri,
+        scope,
+        state: params.state.clone(),
+        code_challenge,
+    })
+}
+
+/// The user already signed in to this service, identified by the refresh
+/// token cookie of a regular login session, and when that session started.
+pub(crate) async fn signed_in_user(conn: &mut PgConnection, cookie: &Option<TypedHeader<Cookie>>) -> Option<(User, NaiveDateTime)> {
+    let raw_token = cookie.as_ref()?.get("refresh_token")?;
+    validate_refresh_token(raw_token).await.ok()?;
+
+    let session = sessions::table
+        .filter(sessions::refresh_token.eq(hash_token(raw_token)))
+        .filter(sessions::client_id.is_null())
+        .first::<Session>(conn)
+        .ok()?;
+
+    let user = users::table
+        .find(session.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(conn)
+        .ok()
+        .filter(can_sign_in)?;
+
+    Some((user, session.created_at.unwrap_or_else(|| Utc::now().naive_utc())))
+}
+
+/// Checks a password sign-in on the authorization page with the same rules
+/// as `/api/login`. Accounts with a second factor have to sign in there first.
+pub(crate) fn sign_in(conn: &mut PgConnection, username: &str, password: &str) -> Result<User, &'static str> {
+    let user = find_user(conn, username).ok_or("Invalid credentials")?;
+    if is_account_locked(&user, Utc::now().naive_utc()) {
+        return Err("Account locked. Try again later.");
+    }
+    if !verify(password, &user.password_hash).unwrap_or(false) {
+        update_login_attempts(conn, &user.username, user.login_attempts + 1);
+        return Err("Invalid credentials");
+    }
+    if user.email_verified_at.is_none() && !allow_unverified_login() {
+        return Err("Email address not verified");
+    }
+    if !can_sign_in(&user) {
+        return Err("Account is disabled");
+    }
+    if user.mfa_type.is_some() {
+        return Err("This account uses two-factor authentication. Sign in to Rusted-Lock first, then try again.");
+    }
+
+    update_login_attempts(conn, &user.username, 0);
+    Ok(user)
+}
+
+/// Renders the login/consent page. The CSRF token is bound to a SameSite
+/// cookie so the form cannot be submitted from another site.
+fn consent_page(params: &AuthorizeParams, request: &ValidRequest, user: Option<&User>, error: Option<&str>) -> Response {
+    let csrf_token = generate_token();
+
+    let hidden = [
+        ("response_type", &params.response_type),
+        ("client_id", &params.client_id),
+        ("redirect_uri", &params.redirect_uri),
+        ("scope", &params.scope),
+        ("state", &params.state),
+        ("code_challenge", &params.code_challenge),
+        ("code_challenge_method", &params.code_challenge_method),
+        ("nonce", &params.nonce),
+    ]
+    .iter()
+    .filter_map(|(name, value)| value.as_ref().map(|v| format!(
+        "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+        name,
+        escape_html(v)
+    )))
+    .collect::<String>();
+
+    let identity = match user {
+        Some(user) => format!("<p>Signed in as <strong>{}</strong>.</p>", escape_html(&user.username)),
+        None => "<p><input name=\"username\" placeholder=\"Username\" autocomplete=\"username\" required></p>\
+                 <p><input name=\"password\" type=\"password\" placeholder=\"Password\" autocomplete=\"current-password\" required></p>".to_string(),
+    };
+    let scopes = request.scope
+        .split_whitespace()
+        .map(|scope| format!("<li>{}</li>", escape_html(scope)))
+        .collect::<String>();
+    let error = error
+        .map(|e| format!("<p style=\"color:#b00020\">{}</p>", escape_html(e)))
+        .unwrap_or_default();
+
+    let page = format!(
+        "<!DOCTYPE html><html><head><title>Authorize {name}</title></head><body>\
+         <h1>Authorize {name}</h1>\
+         <p><strong>{name}</strong> is asking for access to your account.</p>\
+         <ul>{scopes}</ul>{error}\
+         <form method=\"post\" action=\"/oauth/authorize\">{hidden}\
+         <input type=\"hidden\" name=\"csrf_token\" value=\"{csrf}\">{identity}\
+         <button name=\"decision\" value=\"approve\">Allow</button> \
+         <button name=\"decision\" value=\"deny\" formnovalidate>Deny</button>\
+         </form></body></html>",
+        name = escape_html(&request.client.name),
+        scopes = scopes,
+        error = error,
+        hidden = hidden,
+        csrf = csrf_token,
+        identity = identity,
+    );
+
+    let mut response = Html(page).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&format!(
+            "{}={}; HttpOnly; SameSite=Strict; Path=/oauth/authorize",
+            CSRF_COOKIE, csrf_token
+        )).unwrap(),
+    );
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+/// `GET /oauth/authorize` – validates the request and shows the login/consent page.
+pub async fn authorize(
+    State(pool): State<PgPool>,
+    cookie: Option<TypedHeader<Cookie>>,
+    Query(params): Query<AuthorizeParams>,
+) -> Response {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(_) => return error_page("Database error"),
+    };
+
+    let request = match validate_request(&mut conn, &params) {
+        Ok(request) => request,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let user = signed_in_user(&mut conn, &cookie).await.map(|(user, _)| user);
+
+    consent_page(&params, &request, user.as_ref(), None)
+}
+
+/// `POST /oauth/authorize` – handles the user's decision and redirects back
+/// to the client with an authorization code.
+pub async fn authorize_decision(
+    State(pool): State<PgPool>,
+    cookie: Option<TypedHeader<Cookie>>,
+    Form(form): Form<AuthorizeForm>,
+) -> Response {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(_) => return error_page("Database error"),
+    };
+
+    let request = match validate_request(&mut conn, &form.params) {
+        Ok(request) => request,
+        Err(rejection) => return rejection.into_response(),
+    };
+
+    let csrf_cookie = cookie.as_ref().and_then(|c| c.get(CSRF_COOKIE));
+    if csrf_cookie != Some(form.csrf_token.as_str()) {
+        return error_page("The form has expired. Start the sign-in again.");
+    }
+
+    if form.decision != "approve" {
+        return redirect_error(&request, "access_denied", "The user denied the request");
+    }
+
+    let (user, auth_time) = match signed_in_user(&mut conn, &cookie).await {
+        Some(signed_in) => signed_in,
+        None => {
+            let (username, password) = match (form.username.as_deref(), form.password.as_deref()) {
+                (Some(username), Some(password)) => (username, password),
+                _ => return consent_page(&form.params, &request, None, Some("Enter your username and password")),
+            };
+            match sign_in(&mut conn, username, password) {
+                Ok(user) => (user, Utc::now().naive_utc()),
+                Err(message) => return consent_page(&form.params, &request, None, Some(message)),
+            }
+        },
+    };
+
+    let approval = Approval {
+        client_id: request.client.client_id.clone(),
+        user_id: user.id,
+        redirect_uri: request.redirect_uri.clone(),
+        scope: request.scope.clone(),
+        code_challenge: request.code_challenge.clone(),
+        nonce: form.params.nonce.clone(),
+        auth_time,
+        redirect_uri_supplied: form.params.redirect_uri.is_some(),
+    };
+    match create_authorization_code(&mut conn, approval) {
+        Ok(code) => redirect_to_client(&request.redirect_uri, &[("code", &code)], request.state.as_deref()),
+        Err(_) => redirect_error(&request, "server_error", "Failed to issue authorization code"),
+    }
+}
@@ -0,0 +1,177 @@
+// src/handlers/device.rs
+
+use axum::{
+    extract::{Form, Query, State},
+    http::{header, HeaderValue},
+    response::{Html, IntoResponse, Response},
+};
+use axum_extra::TypedHeader;
+use axum_extra::headers::Cookie;
+use serde::Deserialize;
+use chrono::Utc;
+use crate::db::PgPool;
+use crate::handlers::authorize::{error_page, escape_html, sign_in, signed_in_user};
+use crate::models::{OAuthClient, OAuthDeviceCode, User};
+use crate::utils::device_code::{decide_request, find_pending_request, normalize_user_code};
+use crate::utils::oauth_client::find_client;
+use crate::utils::secure_token::generate_token;
+
+const CSRF_COOKIE: &str = "device_csrf";
+
+#[derive(Deserialize, Debug)]
+pub struct VerificationParams {
+    user_code: Option<String>,
+}
+
+/// The code entry form, and the consent form that follows it once `decision` is set.
+#[derive(Deserialize, Debug)]
+pub struct VerificationForm {
+    csrf_token: String,
+    user_code: String,
+    username: Option<String>,
+    password: Option<String>,
+    decision: Option<String>,
+}
+
+/// Wraps `body` in a page with a fresh CSRF token bound to a SameSite cookie.
+fn render(title: &str, body: impl FnOnce(&str) -> String) -> Response {
+    let csrf_token = generate_token();
+    let page = format!(
+        "<!DOCTYPE html><html><head><title>{title}</title></head><body><h1>{title}</h1>{body}</body></html>",
+        title = escape_html(title),
+        body = body(&csrf_token),
+    );
+
+    let mut response = Html(page).into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&format!(
+            "{}={}; HttpOnly; SameSite=Strict; Path=/oauth/device",
+            CSRF_COOKIE, csrf_token
+        )).unwrap(),
+    );
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
+
+fn error_message(error: Option<&str>) -> String {
+    error
+        .map(|e| format!("<p style=\"color:#b00020\">{}</p>", escape_html(e)))
+        .unwrap_or_default()
+}
+
+/// Asks for the code shown on the device.
+fn code_page(user_code: Option<&str>, error: Option<&str>) -> Response {
+    render("Connect a device", |csrf| format!(
+        "<p>Enter the code shown on your device.</p>{error}\
+         <form method=\"post\" action=\"/oauth/device\">\
+         <input type=\"hidden\" name=\"csrf_token\" value=\"{csrf}\">\
+         <p><input name=\"user_code\" value=\"{code}\" placeholder=\"XXXX-XXXX\" autocomplete=\"off\" required></p>\
+         <button>Continue</button>\
+         </form>",
+        error = error_message(error),
+        csrf = csrf,
+        code = escape_html(user_code.unwrap_or_default()),
+    ))
+}
+
+/// Shows which client is asking for which scopes, so the user can check it
+/// matches the device in front of them.
+fn consent_page(request: &OAuthDeviceCode, client: &OAuthClient, user: Option<&User>, error: Option<&str>) -> Response {
+    let identity = match user {
+        Some(user) => format!("<p>Signed in as <strong>{}</strong>.</p>", escape_html(&user.username)),
+        None => "<p><input name=\"username\" placeholder=\"Username\" autocomplete=\"username\" required></p>\
+                 <p><input name=\"password\" type=\"password\" placeholder=\"Password\" autocomplete=\"current-password\" required></p>".to_string(),
+    };
+    let scopes = request.scope
+        .split_whitespace()
+        .map(|scope| format!("<li>{}</li>", escape_html(scope)))
+        .collect::<String>();
+
+    render(&format!("Authorize {}", client.name), |csrf| format!(
+        "<p><strong>{name}</strong> is asking for access to your account from the device showing \
+         <strong>{code}</strong>.</p>\
+         <ul>{scopes}</ul>{error}\
+         <form method=\"post\" action=\"/oauth/device\">\
+         <input type=\"hidden\" name=\"csrf_token\" value=\"{csrf}\">\
+         <input type=\"hidden\" name=\"user_code\" value=\"{code}\">{identity}\
+         <button name=\"decision\" value=\"approve\">Allow</button> \
+         <button name=\"decision\" value=\"deny\" formnovalidate>Deny</button>\
+         </form>",
+        name = escape_html(&client.name),
+        code = escape_html(&request.user_code),
+        scopes = scopes,
+        error = error_message(error),
+        csrf = csrf,
+        identity = identity,
+    ))
+}
+
+fn done_page(message: &str) -> Response {
+    render("Connect a device", |_| format!("<p>{}</p>", escape_html(message)))
+}
+
+/// `GET /oauth/device` – the verification URI. `user_code` is prefilled when
+/// the device shows `verification_uri_complete`.
+pub async fn verification(Query(params): Query<VerificationParams>) -> Response {
+    code_page(params.user_code.as_deref(), None)
+}
+
+/// `POST /oauth/device` – looks up the entered code, then records the user's
+/// decision. The device picks up the outcome on its next poll.
+pub async fn verification_decision(
+    State(pool): State<PgPool>,
+    cookie: Option<TypedHeader<Cookie>>,
+    Form(form): Form<VerificationForm>,
+) -> Response {
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(_) => return error_page("Database error"),
+    };
+
+    let csrf_cookie = cookie.as_ref().and_then(|c| c.get(CSRF_COOKIE));
+    if csrf_cookie != Some(form.csrf_token.as_str()) {
+        return code_page(Some(&form.user_code), Some("The form has expired. Enter the code again."));
+    }
+
+    let request = match normalize_user_code(&form.user_code)
+        .and_then(|code| find_pending_request(&mut conn, &code).ok().flatten())
+    {
+        Some(request) => request,
+        None => return code_page(Some(&form.user_code), Some("Invalid or expired code")),
+    };
+    let client = match find_client(&mut conn, &request.client_id) {
+        Ok(Some(client)) => client,
+        _ => return code_page(None, Some("Invalid or expired code")),
+    };
+
+    let signed_in = signed_in_user(&mut conn, &cookie).await;
+
+    let approver = match form.decision.as_deref() {
+        None => return consent_page(&request, &client, signed_in.as_ref().map(|(user, _)| user), None),
+        Some("approve") => match signed_in {
+            Some((user, auth_time)) => Some((user.id, auth_time)),
+            None => {
+                let (username, password) = match (form.username.as_deref(), form.password.as_deref()) {
+                    (Some(username), Some(password)) => (username, password),
+                    _ => return consent_page(&request, &client, None, Some("Enter your username and password")),
+                };
+                match sign_in(&mut conn, username, password) {
+                    Ok(user) => Some((user.id, Utc::now().naive_utc())),
+                    Err(message) => return consent_page(&request, &client, None, Some(message)),
+                }
+            },
+        },
+        Some(_) => None,
+    };
+
+    let approved = approver.is_some();
+    match decide_request(&mut conn, &request, approver) {
+        Ok(true) if approved => done_page("Your device is connected. You can return to it now."),
+        Ok(true) => done_page("The request was denied. Nothing was shared with the device."),
+        Ok(false) => code_page(None, Some("Invalid or expired code")),
+        Err(_) => error_page("Database error"),
+    }
+}
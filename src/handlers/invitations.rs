@@ -0,0 +1,177 @@
+// src/handlers/invitations.rs
+
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use validator::Validate;
+use diesel::prelude::*;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::db::PgPool;
+use crate::handlers::organizations::require_manager;
+use crate::handlers::register::{create_account, RegisterRequest};
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{Organization, OrganizationInvitation, User};
+use crate::schema::{organization_members, organizations, users};
+use crate::utils::email::send_invitation_email;
+use crate::utils::error::AppError;
+use crate::utils::invitation::{accept_invitation as take_invitation, create_invitation, find_invitation, pending_invitations, revoke_invitation};
+use crate::utils::organizations::{add_member, ORG_ROLES};
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct InvitationRequest {
+    #[validate(email(message = "Invalid email address"))]
+    email: String,
+    role: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AcceptInvitationRequest {
+    token: String,
+    // Only needed when no account exists for the invited address yet
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// Invitation as returned by the API, without the token hash.
+fn invitation_response(invitation: &OrganizationInvitation) -> serde_json::Value {
+    json!({
+        "id": invitation.id,
+        "organization_id": invitation.organization_id,
+        "email": invitation.email,
+        "role": invitation.role,
+        "invited_by": invitation.invited_by,
+        "expires_at": invitation.expires_at,
+        "created_at": invitation.created_at,
+    })
+}
+
+pub async fn invite_member(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(org_id): Path<Uuid>,
+    Json(req): Json<InvitationRequest>,
+) -> Result<Response, AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    let role = req.role.unwrap_or_else(|| "member".to_string());
+    if !ORG_ROLES.contains(&role.as_str()) {
+        return Err(AppError::ValidationError(format!("Invalid role: {}", role)));
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let actor = require_manager(&mut conn, org_id, &auth)?;
+    if role == "owner" && actor.role != "owner" {
+        return Err(AppError::Forbidden("Only owners can change ownership".to_string()));
+    }
+
+    let already_member = organization_members::table
+        .inner_join(users::table)
+        .filter(organization_members::organization_id.eq(org_id))
+        .filter(users::email.eq(&req.email))
+        .count()
+        .get_result::<i64>(&mut conn)? > 0;
+    if already_member {
+        return Err(AppError::ValidationError("User is already a member".to_string()));
+    }
+
+    let organization = organizations::table.find(org_id).first::<Organization>(&mut conn)?;
+    let (invitation, invitation_token) = create_invitation(&mut conn, org_id, &req.email, &role, auth.user_id)?;
+
+    send_invitation_email(&invitation.email, &organization.name, &invitation_token, invitation.expires_at)
+        .await
+        .map_err(|e| AppError::EmailError(format!("Failed to send invitation: {}", e)))?;
+
+    Ok((StatusCode::CREATED, Json(invitation_response(&invitation))).into_response())
+}
+
+/// Invitations that have not been accepted, revoked or expired.
+pub async fn list_invitations(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    require_manager(&mut conn, org_id, &auth)?;
+    let invitations: Vec<_> = pending_invitations(&mut conn, org_id)?
+        .iter()
+        .map(invitation_response)
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "invitations": invitations }))).into_response())
+}
+
+pub async fn revoke(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path((org_id, invitation_id)): Path<(Uuid, i32)>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    require_manager(&mut conn, org_id, &auth)?;
+    if !revoke_invitation(&mut conn, org_id, invitation_id)? {
+        return Err(AppError::NotFound("Invitation not found".to_string()));
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Invitation revoked" }))).into_response())
+}
+
+/// Accepts an invitation. An existing account with the invited address joins
+/// the organization; otherwise a new account is registered for that address.
+pub async fn accept_invitation(
+    State(pool): State<PgPool>,
+    Json(req): Json<AcceptInvitationRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let invitation = find_invitation(&mut conn, &req.token)?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired invitation".to_string()))?;
+
+    let existing = users::table
+        .filter(users::email.eq(&invitation.email))
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?;
+
+    let (status, message) = conn.transaction::<_, AppError, _>(|conn| {
+        let invitation = take_invitation(conn, &req.token)?
+            .ok_or_else(|| AppError::ValidationError("Invalid or expired invitation".to_string()))?;
+
+        let (user, status, message) = match existing {
+            Some(user) => (user, StatusCode::OK, "Invitation accepted"),
+            None => {
+                let (Some(username), Some(password)) = (req.username, req.password) else {
+                    return Err(AppError::ValidationError("Username and password are required to create an account".to_string()));
+                };
+                let user = create_account(conn, RegisterRequest {
+                    username,
+                    password,
+                    email: invitation.email.clone(),
+                })?;
+
+                // The invitation link already proved control of the address
+                let user = diesel::update(users::table.find(user.id))
+                    .set((
+                        users::email_verified_at.eq(Utc::now().naive_utc()),
+                        users::status.eq("active"),
+                    ))
+                    .get_result::<User>(conn)?;
+                (user, StatusCode::CREATED, "Account created and invitation accepted")
+            },
+        };
+
+        add_member(conn, invitation.organization_id, user.id, &invitation.role)?;
+        Ok((status, message))
+    })?;
+
+    Ok((status, Json(json!({
+        "message": message,
+        "organization_id": invitation.organization_id,
+    }))).into_response())
+}
@@ -13,11 +13,19 @@ use serde::{Deserialize, Serialize};
 use bcrypt::verify;
 use diesel::prelude::*;
 use chrono::{Utc, Duration};
+use uuid::Uuid;
 use crate::models::User;
-use crate::schema::users::dsl::{users, username};
+use crate::schema::users::dsl::{users, username, deleted_at};
 use crate::db::PgPool;
+use crate::config::allow_unverified_login;
 use crate::schema::sessions::dsl::sessions;
+use crate::utils::account_status::can_sign_in;
 use crate::utils::jwt::{generate_jwt, update_login_attempts};
+use crate::utils::email::send_login_code_email;
+use crate::utils::permissions::{resolve_grants, Grants};
+use crate::utils::secure_token::hash_token;
+use crate::utils::token_family::record_refresh_token;
+use crate::utils::mfa_challenge::{create_challenge, generate_email_code, CHALLENGE_LIFETIME_MINUTES};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct LoginRequest {
@@ -41,6 +49,15 @@ pub async fn login(
             }
 
             if verify(login_info.password, &user.password_hash).unwrap_or(false) {
+                if user.email_verified_at.is_none() && !allow_unverified_login() {
+                    return Ok((StatusCode::FORBIDDEN, "Email address not verified".to_string()).into_response());
+                }
+                if !can_sign_in(&user) {
+                    return Ok((StatusCode::FORBIDDEN, "Account is disabled".to_string()).into_response());
+                }
+                if let Some(mfa_type) = &user.mfa_type {
+                    return Ok(mfa_challenge(&mut conn, &user, mfa_type).await);
+                }
                 Ok(successful_login(&mut conn, &user).await)
             } else {
                 let attempts = user.login_attempts + 1;
@@ -53,74 +70,122 @@ pub async fn login(
     Ok(login_attempts_count)
 }
 
-fn find_user(conn: &mut PgConnection, user_name: &str) -> Option<User> {
+pub(crate) fn find_user(conn: &mut PgConnection, user_name: &str) -> Option<User> {
     users
         .filter(username.eq(user_name))
+        .filter(deleted_at.is_null())
         .first::<User>(conn)
         .optional()
         .expect("Error loading user")
 }
 
-async fn handle_user_login(
-    conn: &mut PgConnection,
-    user: User,
-    password: &str,
-) -> Response<Body> {
-    let now = Utc::now().naive_utc();
-    let mut login_attempts_count = user.login_attempts + 1;
+pub(crate) fn is_account_locked(user: &User, now: chrono::NaiveDateTime) -> bool {
+    user.login_attempts > 3 && user.last_login_at.is_some_and(|last_attempt| {
+        now.signed_duration_since(last_attempt) < Duration::minutes(env::var("ACCOUNT_LOCK_DURATION").unwrap().parse::<i64>().unwrap())
+    })
+}
 
-    if is_account_locked(&user, now) {
-        return (StatusCode::FORBIDDEN, "Account locked. Try again later.".to_string()).into_response();
-    }
+/// Holds the login back until the second factor is verified at `/api/login/mfa`.
+/// For the email factor the one-time code is sent here.
+pub(crate) async fn mfa_challenge(conn: &mut PgConnection, user: &User, mfa_type: &str) -> Response<Body> {
+    let email_code = (mfa_type == "email").then(generate_email_code);
 
-    if verify(password, &user.password_hash).unwrap() {
-        successful_login(conn, &user).await
-    } else {
-        failed_login(conn, &user, login_attempts_count).await
+    let challenge_token = match create_challenge(conn, user.id, email_code.as_deref()) {
+        Ok(challenge_token) => challenge_token,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create MFA challenge".to_string()).into_response()
+    };
+
+    if let Some(code) = &email_code {
+        let code_expiration = Utc::now().naive_utc() + Duration::minutes(CHALLENGE_LIFETIME_MINUTES);
+        if send_login_code_email(&user.email, code, code_expiration).await.is_err() {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to send login code".to_string()).into_response();
+        }
     }
-}
 
-fn is_account_locked(user: &User, now: chrono::NaiveDateTime) -> bool {
-    user.login_attempts > 3 && user.last_login_at.map_or(false, |last_attempt| {
-        now.signed_duration_since(last_attempt) < Duration::minutes(env::var("ACCOUNT_LOCK_DURATION").unwrap().parse::<i64>().unwrap())
-    })
+    let challenge_resp = serde_json::json!({
+        "message": "MFA required",
+        "mfa_required": true,
+        "mfa_type": mfa_type,
+        "challenge_token": challenge_token,
+    });
+
+    (StatusCode::OK, Json(challenge_resp)).into_response()
 }
 
-async fn successful_login(conn: &mut PgConnection, user: &User) -> Response<Body> {
+pub(crate) async fn successful_login(conn: &mut PgConnection, user: &User) -> Response<Body> {
     update_login_attempts(conn, &user.username, 0);
 
-    let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_key".to_string());
-    let jwt_secret_x = env::var("JWT_SECRET_X").unwrap_or_else(|_| "default_refresh_secret_key".to_string());
-    
-    let access_token = match generate_jwt(user.username.clone(), &jwt_secret, false) {
-        Ok(token) => token,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate access token".to_string()).into_response()
-    };
-    let refresh_token = match generate_jwt(user.username.clone(), &jwt_secret_x, true) {
-        Ok(token) => token,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate refresh token".to_string()).into_response()
-    };
+    start_session(conn, user, None, "Login successful").await
+}
+
+/// Tokens of a newly recorded session.
+pub(crate) struct IssuedTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub family_id: Uuid,
+}
+
+/// Signs a token pair for `grants` and records the session. Every session
+/// starts a new refresh token family.
+pub(crate) fn create_session(conn: &mut PgConnection, user: &User, grants: &Grants) -> Result<IssuedTokens, &'static str> {
+    let access_token = generate_jwt(user.id, grants, false)
+        .map_err(|_| "Failed to generate access token")?;
+    let refresh_token = generate_jwt(user.id, grants, true)
+        .map_err(|_| "Failed to generate refresh token")?;
 
     let new_session = crate::models::NewSession {
         user_id: user.id,
-        token: access_token.clone(),
-        refresh_token: refresh_token.clone(),
+        token: hash_token(&access_token),
+        refresh_token: hash_token(&refresh_token),
         expires_at: Utc::now().naive_utc() + Duration::minutes(
             env::var("ACCESS_TOKEN_EXP_DURATION")
                 .unwrap_or_else(|_| "15".to_string())
                 .parse::<i64>()
                 .unwrap_or(15)
         ),
+        organization_id: grants.organization_id,
+        family_id: Uuid::new_v4(),
+        client_id: grants.client_id.clone(),
+        scope: grants.scope.clone(),
     };
 
-    diesel::insert_into(sessions)
-        .values(&new_session)
-        .execute(conn)
-        .expect("Error saving session");
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::insert_into(sessions)
+            .values(&new_session)
+            .execute(conn)?;
+        record_refresh_token(conn, new_session.family_id, user.id, &refresh_token)
+    }).map_err(|_| "Failed to save session")?;
+
+    Ok(IssuedTokens { access_token, refresh_token, family_id: new_session.family_id })
+}
+
+/// Issues a token pair acting in `organization` (or the user's default
+/// organization) and records the session.
+pub(crate) async fn start_session(
+    conn: &mut PgConnection,
+    user: &User,
+    organization: Option<Uuid>,
+    message: &str,
+) -> Response<Body> {
+    if !can_sign_in(user) {
+        return (StatusCode::FORBIDDEN, "Account is disabled".to_string()).into_response();
+    }
+
+    let grants = match resolve_grants(conn, user, organization) {
+        Ok(grants) => grants,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to resolve permissions".to_string()).into_response()
+    };
+
+    let IssuedTokens { access_token, refresh_token, .. } = match create_session(conn, user, &grants) {
+        Ok(tokens) => tokens,
+        Err(message) => return (StatusCode::INTERNAL_SERVER_ERROR, message.to_string()).into_response()
+    };
 
     let login_resp = serde_json::json!({
-        "message": "Login successful",
+        "message": message,
         "token": access_token,
+        "email_verified": user.email_verified_at.is_some(),
+        "organization_id": grants.organization_id,
     });
 
     let mut headers = HeaderMap::new();
@@ -6,9 +6,8 @@ use axum::{
 use axum_extra::headers::Authorization;
 use axum_extra::headers::authorization::Bearer;
 use axum_extra::TypedHeader;
-use diesel::prelude::*;
-use crate::schema::sessions::dsl::*;
 use crate::db::PgPool;
+use crate::utils::jwt_validator::invalidate_token;
 
 pub async fn logout(
     State(pool): State<PgPool>,
@@ -21,10 +20,8 @@ pub async fn logout(
         Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
     };
 
-    // Delete the session from database to invalidate both tokens
-    match diesel::delete(sessions.filter(token.eq(access_token)))
-        .execute(&mut conn)
-    {
+    // Revoke the session and its refresh token family to invalidate both tokens
+    match invalidate_token(&mut conn, access_token, Some("access_token"), None) {
         Ok(_) => {
             // Return Set-Cookie header to clear the refresh token cookie
             let cookie = "refresh_token=; HttpOnly; Path=/; Max-Age=0";
@@ -0,0 +1,89 @@
+// src/handlers/magic_link.rs
+
+use std::net::SocketAddr;
+use axum::{
+    extract::{ConnectInfo, Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use crate::config::allow_unverified_login;
+use crate::db::PgPool;
+use crate::handlers::login::{mfa_challenge, successful_login};
+use crate::models::User;
+use crate::schema::users;
+use crate::utils::account_status::can_sign_in;
+use crate::utils::email::send_magic_link_email;
+use crate::utils::error::AppError;
+use crate::utils::magic_link::{consume_magic_link_token, create_magic_link_token};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MagicLinkRequest {
+    email: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MagicLinkCallbackRequest {
+    token: String,
+}
+
+pub async fn request_magic_link(
+    State(pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<MagicLinkRequest>,
+) -> Result<(StatusCode, &'static str), AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table
+        .filter(users::email.eq(&req.email))
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?;
+
+    if let Some(user) = user {
+        let (login_token, token_expiration) = create_magic_link_token(&mut conn, &user, Some(addr.ip().to_string()))?;
+
+        send_magic_link_email(&user.email, &login_token, token_expiration)
+            .await
+            .map_err(|e| AppError::EmailError(format!("Failed to send login link: {}", e)))?;
+    }
+
+    // Don't reveal if the user exists or not for security reasons
+    Ok((StatusCode::OK, "If an account with that email exists, you will receive a login link"))
+}
+
+/// Exchanges a magic link token for the same session a password login produces.
+pub async fn magic_link_callback(
+    State(pool): State<PgPool>,
+    Json(req): Json<MagicLinkCallbackRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let login_token = consume_magic_link_token(&mut conn, &req.token)?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired login link".to_string()))?;
+
+    let user = users::table
+        .find(login_token.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired login link".to_string()))?;
+
+    // The link only proves control of the address it was sent to
+    if !user.email.eq_ignore_ascii_case(&login_token.email) {
+        return Err(AppError::ValidationError("Invalid or expired login link".to_string()));
+    }
+
+    if user.email_verified_at.is_none() && !allow_unverified_login() {
+        return Ok((StatusCode::FORBIDDEN, "Email address not verified".to_string()).into_response());
+    }
+    if !can_sign_in(&user) {
+        return Ok((StatusCode::FORBIDDEN, "Account is disabled".to_string()).into_response());
+    }
+    if let Some(mfa_type) = &user.mfa_type {
+        return Ok(mfa_challenge(&mut conn, &user, mfa_type).await);
+    }
+
+    Ok(successful_login(&mut conn, &user).await)
+}
@@ -0,0 +1,260 @@
+// src/handlers/mfa.rs
+
+use axum::{
+    extract::{Extension, Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use bcrypt::verify;
+use diesel::prelude::*;
+use chrono::Utc;
+use crate::db::PgPool;
+use crate::handlers::login::successful_login;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{NewTotpSecret, TotpSecret, User};
+use crate::schema::{totp_secrets, users};
+use crate::utils::error::AppError;
+use crate::utils::mfa_challenge::{consume_challenge, find_challenge, record_failed_attempt, verify_email_code};
+use crate::utils::recovery_codes::{consume_recovery_code, count_remaining_codes, generate_recovery_codes};
+use crate::utils::totp::{build_totp, generate_totp_secret, verify_totp_code};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ConfirmTotpRequest {
+    code: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MfaLoginRequest {
+    challenge_token: String,
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct EnableEmailMfaRequest {
+    password: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RegenerateRecoveryCodesRequest {
+    password: String,
+}
+
+/// Starts TOTP enrollment by generating a new secret. MFA is not enabled until
+/// the first code is confirmed.
+pub async fn enroll_totp(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table.find(auth.user_id).first::<User>(&mut conn)?;
+    if user.mfa_type.is_some() {
+        return Err(AppError::ValidationError("MFA is already enabled".to_string()));
+    }
+
+    let secret = generate_totp_secret();
+    let totp = build_totp(&secret, &user.email)?;
+
+    diesel::insert_into(totp_secrets::table)
+        .values(&NewTotpSecret {
+            user_id: user.id,
+            secret: secret.clone(),
+        })
+        .on_conflict(totp_secrets::user_id)
+        .do_update()
+        .set((
+            totp_secrets::secret.eq(&secret),
+            totp_secrets::confirmed_at.eq(None::<chrono::NaiveDateTime>),
+            totp_secrets::last_used_step.eq(None::<i64>),
+        ))
+        .execute(&mut conn)?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "secret": secret,
+        "otpauth_url": totp.get_url(),
+    }))).into_response())
+}
+
+/// Confirms enrollment with a first valid code and enables TOTP for the user.
+/// The response carries the initial set of recovery codes.
+pub async fn confirm_totp(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<ConfirmTotpRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table.find(auth.user_id).first::<User>(&mut conn)?;
+    let totp_secret = totp_secrets::table
+        .find(user.id)
+        .filter(totp_secrets::confirmed_at.is_null())
+        .first::<TotpSecret>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::ValidationError("No pending TOTP enrollment".to_string()))?;
+
+    let totp = build_totp(&totp_secret.secret, &user.email)?;
+    let step = verify_totp_code(&totp, &req.code, totp_secret.last_used_step)
+        .ok_or_else(|| AppError::ValidationError("Invalid TOTP code".to_string()))?;
+
+    let recovery_codes = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(totp_secrets::table.find(user.id))
+            .set((
+                totp_secrets::confirmed_at.eq(Utc::now().naive_utc()),
+                totp_secrets::last_used_step.eq(step),
+            ))
+            .execute(conn)?;
+
+        diesel::update(users::table.find(user.id))
+            .set(users::mfa_type.eq("authenticator"))
+            .execute(conn)?;
+
+        generate_recovery_codes(conn, user.id)
+    })?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "message": "TOTP enabled",
+        "recovery_codes": recovery_codes,
+    }))).into_response())
+}
+
+/// Completes a login that was held back by `login` pending the second factor.
+pub async fn login_mfa(
+    State(pool): State<PgPool>,
+    Json(req): Json<MfaLoginRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let challenge = find_challenge(&mut conn, &req.challenge_token)?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired MFA challenge".to_string()))?;
+
+    let user = users::table
+        .find(challenge.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired MFA challenge".to_string()))?;
+
+    // A recovery code stands in for the TOTP code when the authenticator is lost
+    if let Some(recovery_code) = &req.recovery_code {
+        if !consume_recovery_code(&mut conn, user.id, recovery_code)? {
+            record_failed_attempt(&mut conn, &challenge)?;
+            return Err(AppError::ValidationError("Invalid recovery code".to_string()));
+        }
+
+        if !consume_challenge(&mut conn, &challenge)? {
+            return Err(AppError::ValidationError("Invalid or expired MFA challenge".to_string()));
+        }
+
+        return Ok(successful_login(&mut conn, &user).await);
+    }
+
+    let code = req.code
+        .ok_or_else(|| AppError::ValidationError("A verification code or recovery code is required".to_string()))?;
+
+    if user.mfa_type.as_deref() == Some("email") {
+        if !verify_email_code(&challenge, &code) {
+            record_failed_attempt(&mut conn, &challenge)?;
+            return Err(AppError::ValidationError("Invalid verification code".to_string()));
+        }
+
+        if !consume_challenge(&mut conn, &challenge)? {
+            return Err(AppError::ValidationError("Invalid or expired MFA challenge".to_string()));
+        }
+
+        return Ok(successful_login(&mut conn, &user).await);
+    }
+
+    let totp_secret = totp_secrets::table
+        .find(user.id)
+        .filter(totp_secrets::confirmed_at.is_not_null())
+        .first::<TotpSecret>(&mut conn)?;
+
+    let totp = build_totp(&totp_secret.secret, &user.email)?;
+    let step = match verify_totp_code(&totp, &code, totp_secret.last_used_step) {
+        Some(step) => step,
+        None => {
+            record_failed_attempt(&mut conn, &challenge)?;
+            return Err(AppError::ValidationError("Invalid verification code".to_string()));
+        }
+    };
+
+    if !consume_challenge(&mut conn, &challenge)? {
+        return Err(AppError::ValidationError("Invalid or expired MFA challenge".to_string()));
+    }
+
+    diesel::update(totp_secrets::table.find(user.id))
+        .set(totp_secrets::last_used_step.eq(step))
+        .execute(&mut conn)?;
+
+    Ok(successful_login(&mut conn, &user).await)
+}
+
+/// Enables emailed one-time codes as the second factor, for users who cannot
+/// use an authenticator app. The response carries the initial recovery codes.
+pub async fn enable_email_mfa(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<EnableEmailMfaRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table.find(auth.user_id).first::<User>(&mut conn)?;
+    if !verify(&req.password, &user.password_hash).unwrap_or(false) {
+        return Err(AppError::ValidationError("Invalid password".to_string()));
+    }
+    if user.mfa_type.is_some() {
+        return Err(AppError::ValidationError("MFA is already enabled".to_string()));
+    }
+    if user.email_verified_at.is_none() {
+        return Err(AppError::ValidationError("Email address must be verified first".to_string()));
+    }
+
+    let recovery_codes = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(users::table.find(user.id))
+            .set(users::mfa_type.eq("email"))
+            .execute(conn)?;
+
+        generate_recovery_codes(conn, user.id)
+    })?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "message": "Email MFA enabled",
+        "recovery_codes": recovery_codes,
+    }))).into_response())
+}
+
+pub async fn recovery_codes_status(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let remaining = count_remaining_codes(&mut conn, auth.user_id)?;
+
+    Ok((StatusCode::OK, Json(json!({ "remaining": remaining }))).into_response())
+}
+
+/// Issues a new set of recovery codes, invalidating the old ones.
+/// The user must re-enter their password.
+pub async fn regenerate_recovery_codes(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<RegenerateRecoveryCodesRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table.find(auth.user_id).first::<User>(&mut conn)?;
+    if !verify(&req.password, &user.password_hash).unwrap_or(false) {
+        return Err(AppError::ValidationError("Invalid password".to_string()));
+    }
+    if user.mfa_type.is_none() {
+        return Err(AppError::ValidationError("MFA is not enabled".to_string()));
+    }
+
+    let recovery_codes = generate_recovery_codes(&mut conn, user.id)?;
+
+    Ok((StatusCode::OK, Json(json!({ "recovery_codes": recovery_codes }))).into_response())
+}
@@ -1,5 +1,21 @@
 pub mod login;
 pub mod register;
 pub mod logout;
-mod refresh;
+pub(crate) mod refresh;
 pub(crate) mod forgot;
+pub(crate) mod reset;
+pub(crate) mod verify;
+pub(crate) mod mfa;
+pub(crate) mod webauthn;
+pub(crate) mod magic_link;
+pub(crate) mod users;
+pub(crate) mod roles;
+pub(crate) mod organizations;
+pub(crate) mod invitations;
+pub(crate) mod well_known;
+pub(crate) mod signing_keys;
+pub(crate) mod oauth;
+pub(crate) mod oauth_clients;
+pub(crate) mod authorize;
+pub(crate) mod userinfo;
+pub(crate) mod device;
@@ -0,0 +1,511 @@
+// src/handlers/oauth.rs
+
+use axum::{
+    extract::{Form, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::TypedHeader;
+use axum_extra::headers::Authorization;
+use axum_extra::headers::authorization::Basic;
+use serde::Deserialize;
+use serde_json::json;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use crate::config::get_public_url;
+use crate::db::PgPool;
+use crate::handlers::login::create_session;
+use crate::models::{OAuthClient, Session, User};
+use crate::schema::{sessions, users};
+use crate::utils::account_status::can_sign_in;
+use crate::utils::authorization_code::{attach_family, consume_authorization_code, find_redeemed_code, verify_code_challenge};
+use crate::utils::device_code::{create_device_code, poll_device_code, PollOutcome, DEVICE_CODE_LIFETIME_MINUTES};
+use crate::utils::gen_refresh_token::refresh_tokens;
+use crate::utils::id_token::{generate_id_token, has_scope, oidc_enabled};
+use crate::utils::jwt::{client_token_lifetime_minutes, generate_client_jwt, token_lifetime_minutes, Claims};
+use crate::utils::jwt_validator::{decode_access_token, invalidate_token, validate_refresh_token};
+use crate::utils::oauth_client::{authenticate_client, find_client, find_client_token, grant_scope, is_public, record_client_token};
+use crate::utils::permissions::{resolve_grants, scope_permissions};
+use crate::utils::secure_token::hash_token;
+use crate::utils::security_event::{record_security_event, AUTHORIZATION_CODE_REUSE};
+use crate::utils::token_family::{find_refresh_token, is_spent, revoke_family};
+
+#[derive(Deserialize, Debug)]
+pub struct TokenRequest {
+    grant_type: String,
+    // authorization_code
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    code_verifier: Option<String>,
+    // refresh_token
+    refresh_token: Option<String>,
+    // client_credentials
+    scope: Option<String>,
+    // urn:ietf:params:oauth:grant-type:device_code
+    device_code: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeviceAuthorizationRequest {
+    scope: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RevokeRequest {
+    token: String,
+    token_type_hint: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct IntrospectRequest {
+    token: String,
+    token_type_hint: Option<String>,
+    // client_secret_post; HTTP Basic is preferred
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// Error responses as defined in RFC 6749 section 5.2.
+#[derive(Debug)]
+pub enum OAuthError {
+    InvalidRequest(String),
+    InvalidClient,
+    InvalidGrant(String),
+    UnauthorizedClient(String),
+    UnsupportedGrantType,
+    InvalidScope,
+    ServerError(String),
+    // Device authorization grant (RFC 8628 section 3.5)
+    AuthorizationPending,
+    SlowDown,
+    AccessDenied,
+    ExpiredToken,
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        let (status, error, description) = match self {
+            OAuthError::InvalidRequest(e) => (StatusCode::BAD_REQUEST, "invalid_request", e),
+            OAuthError::InvalidClient => (StatusCode::UNAUTHORIZED, "invalid_client", "Client authentication failed".to_string()),
+            OAuthError::InvalidGrant(e) => (StatusCode::BAD_REQUEST, "invalid_grant", e),
+            OAuthError::UnauthorizedClient(e) => (StatusCode::BAD_REQUEST, "unauthorized_client", e),
+            OAuthError::UnsupportedGrantType => (StatusCode::BAD_REQUEST, "unsupported_grant_type", "Unsupported grant type".to_string()),
+            OAuthError::InvalidScope => (StatusCode::BAD_REQUEST, "invalid_scope", "Requested scope is not allowed for this client".to_string()),
+            OAuthError::ServerError(e) => (StatusCode::INTERNAL_SERVER_ERROR, "server_error", e),
+            OAuthError::AuthorizationPending => (StatusCode::BAD_REQUEST, "authorization_pending", "The user has not approved the request yet".to_string()),
+            OAuthError::SlowDown => (StatusCode::BAD_REQUEST, "slow_down", "Polling too fast; wait 5 more seconds between requests".to_string()),
+            OAuthError::AccessDenied => (StatusCode::BAD_REQUEST, "access_denied", "The user denied the request".to_string()),
+            OAuthError::ExpiredToken => (StatusCode::BAD_REQUEST, "expired_token", "The device code has expired".to_string()),
+        };
+
+        let body = Json(json!({
+            "error": error,
+            "error_description": description,
+        }));
+
+        if status == StatusCode::UNAUTHORIZED {
+            (status, [(header::WWW_AUTHENTICATE, "Basic realm=\"oauth\"")], body).into_response()
+        } else {
+            (status, body).into_response()
+        }
+    }
+}
+
+impl From<diesel::result::Error> for OAuthError {
+    fn from(err: diesel::result::Error) -> Self {
+        OAuthError::ServerError(err.to_string())
+    }
+}
+
+/// Authenticates the calling client from HTTP Basic credentials or, failing
+/// that, `client_id` / `client_secret` form fields.
+fn authenticate(
+    conn: &mut PgConnection,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+) -> Result<OAuthClient, OAuthError> {
+    let credentials = match basic {
+        Some(TypedHeader(Authorization(basic))) => Some((basic.username().to_string(), basic.password().to_string())),
+        None => client_id.zip(client_secret),
+    };
+
+    match credentials {
+        Some((id, secret)) => authenticate_client(conn, &id, &secret)?.ok_or(OAuthError::InvalidClient),
+        None => Err(OAuthError::InvalidClient),
+    }
+}
+
+/// Identifies the client at the token endpoint. Confidential clients have to
+/// authenticate; public clients only name themselves and rely on PKCE.
+fn identify_client(
+    conn: &mut PgConnection,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+) -> Result<OAuthClient, OAuthError> {
+    if basic.is_some() || client_secret.is_some() {
+        return authenticate(conn, basic, client_id, client_secret);
+    }
+
+    match client_id {
+        Some(id) => find_client(conn, &id)?
+            .filter(is_public)
+            .ok_or(OAuthError::InvalidClient),
+        None => Err(OAuthError::InvalidClient),
+    }
+}
+
+/// Successful token response (RFC 6749 section 5.1).
+fn token_response(
+    access_token: String,
+    expires_in_minutes: i64,
+    refresh_token: Option<String>,
+    scope: Option<String>,
+    id_token: Option<String>,
+) -> Response {
+    let mut token_resp = json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": expires_in_minutes * 60,
+        "scope": scope,
+    });
+    if let Some(refresh_token) = refresh_token {
+        token_resp["refresh_token"] = json!(refresh_token);
+    }
+    if let Some(id_token) = id_token {
+        token_resp["id_token"] = json!(id_token);
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "no-store"), (header::PRAGMA, "no-cache")],
+        Json(token_resp),
+    ).into_response()
+}
+
+/// Exchanges an authorization code. A code that is presented a second time
+/// revokes the tokens it was first exchanged for (RFC 6749 section 4.1.2).
+fn authorization_code_grant(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    req: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let (raw_code, verifier) = match (req.code, req.code_verifier) {
+        (Some(code), Some(verifier)) => (code, verifier),
+        _ => return Err(OAuthError::InvalidRequest("code and code_verifier are required".to_string())),
+    };
+
+    let code = match consume_authorization_code(conn, &raw_code)? {
+        Some(code) => code,
+        None => {
+            if let Some(redeemed) = find_redeemed_code(conn, &raw_code)? {
+                if let Some(family) = redeemed.family_id {
+                    revoke_family(conn, family)?;
+                }
+                record_security_event(
+                    conn,
+                    Some(redeemed.user_id),
+                    AUTHORIZATION_CODE_REUSE,
+                    format!("Authorization code {} for client {} was presented again", redeemed.id, redeemed.client_id),
+                )?;
+            }
+            return Err(OAuthError::InvalidGrant("Invalid or expired authorization code".to_string()));
+        },
+    };
+
+    // redirect_uri has to be repeated only if the authorization request had it
+    let redirect_uri_matches = match req.redirect_uri.as_deref() {
+        Some(uri) => uri == code.redirect_uri,
+        None => !code.redirect_uri_supplied,
+    };
+    if code.client_id != client.client_id || !redirect_uri_matches {
+        return Err(OAuthError::InvalidGrant("Authorization code was issued for another client or redirect URI".to_string()));
+    }
+    if !verify_code_challenge(&verifier, &code.code_challenge) {
+        return Err(OAuthError::InvalidGrant("PKCE verification failed".to_string()));
+    }
+
+    let user = users::table
+        .find(code.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(conn)
+        .optional()?
+        .ok_or_else(|| OAuthError::InvalidGrant("Account no longer exists".to_string()))?;
+    if !can_sign_in(&user) {
+        return Err(OAuthError::InvalidGrant("Account is disabled".to_string()));
+    }
+
+    let grants = resolve_grants(conn, &user, None)?.for_client(&client.client_id, &code.scope);
+    let tokens = create_session(conn, &user, &grants)
+        .map_err(|e| OAuthError::ServerError(e.to_string()))?;
+    attach_family(conn, &code, tokens.family_id)?;
+
+    // OpenID Connect clients also get an ID token
+    let id_token = if has_scope(Some(&code.scope), "openid") {
+        Some(
+            generate_id_token(&user, &client.client_id, &code.scope, code.nonce.clone(), code.auth_time)
+                .map_err(|e| OAuthError::ServerError(format!("Failed to generate ID token: {}", e)))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(token_response(tokens.access_token, token_lifetime_minutes(false), Some(tokens.refresh_token), Some(code.scope), id_token))
+}
+
+/// Rotates a refresh token issued to this client.
+async fn refresh_token_grant(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    req: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let raw_token = req.refresh_token
+        .ok_or_else(|| OAuthError::InvalidRequest("refresh_token is required".to_string()))?;
+
+    let session = sessions::table
+        .filter(sessions::refresh_token.eq(hash_token(&raw_token)))
+        .first::<Session>(conn)
+        .optional()?;
+    if let Some(session) = &session {
+        if session.client_id.as_deref() != Some(client.client_id.as_str()) {
+            return Err(OAuthError::InvalidGrant("Refresh token was issued to another client".to_string()));
+        }
+    }
+
+    let (access_token, refresh_token) = refresh_tokens(&raw_token, conn, Some(&client.client_id)).await
+        .map_err(OAuthError::InvalidGrant)?;
+
+    Ok(token_response(access_token, token_lifetime_minutes(false), Some(refresh_token), session.and_then(|s| s.scope), None))
+}
+
+/// Issues a short-lived access token to a confidential client acting on its
+/// own behalf (RFC 6749 section 4.4). There is no user and no refresh token;
+/// the scope is limited to what the client is allowed.
+fn client_credentials_grant(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    req: TokenRequest,
+) -> Result<Response, OAuthError> {
+    if is_public(client) {
+        return Err(OAuthError::UnauthorizedClient("Public clients cannot use the client_credentials grant".to_string()));
+    }
+
+    let scope = grant_scope(client, req.scope.as_deref()).ok_or(OAuthError::InvalidScope)?;
+    let permissions = scope_permissions(conn, &scope)?;
+
+    let lifetime = client_token_lifetime_minutes();
+    let access_token = generate_client_jwt(&client.client_id, &scope, permissions)
+        .map_err(|e| OAuthError::ServerError(format!("Failed to generate access token: {}", e)))?;
+    record_client_token(conn, client, &access_token, &scope, Utc::now().naive_utc() + Duration::minutes(lifetime))?;
+
+    Ok(token_response(access_token, lifetime, None, Some(scope), None))
+}
+
+/// Polls for the outcome of a device authorization request. Once the user
+/// approves it, the device gets tokens exactly like after an authorization
+/// code exchange.
+fn device_code_grant(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    req: TokenRequest,
+) -> Result<Response, OAuthError> {
+    let raw_code = req.device_code
+        .ok_or_else(|| OAuthError::InvalidRequest("device_code is required".to_string()))?;
+
+    let request = match poll_device_code(conn, &client.client_id, &raw_code)? {
+        PollOutcome::Approved(request) => request,
+        PollOutcome::Pending => return Err(OAuthError::AuthorizationPending),
+        PollOutcome::SlowDown => return Err(OAuthError::SlowDown),
+        PollOutcome::Denied => return Err(OAuthError::AccessDenied),
+        PollOutcome::Expired => return Err(OAuthError::ExpiredToken),
+        PollOutcome::Invalid => return Err(OAuthError::InvalidGrant("Invalid device code".to_string())),
+    };
+
+    let user = request.user_id
+        .map(|id| users::table.find(id).filter(users::deleted_at.is_null()).first::<User>(conn).optional())
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| OAuthError::InvalidGrant("Account no longer exists".to_string()))?;
+    if !can_sign_in(&user) {
+        return Err(OAuthError::InvalidGrant("Account is disabled".to_string()));
+    }
+
+    let grants = resolve_grants(conn, &user, None)?.for_client(&client.client_id, &request.scope);
+    let tokens = create_session(conn, &user, &grants)
+        .map_err(|e| OAuthError::ServerError(e.to_string()))?;
+
+    let id_token = if has_scope(Some(&request.scope), "openid") {
+        let auth_time = request.auth_time.or(request.approved_at).unwrap_or_else(|| Utc::now().naive_utc());
+        Some(
+            generate_id_token(&user, &client.client_id, &request.scope, None, auth_time)
+                .map_err(|e| OAuthError::ServerError(format!("Failed to generate ID token: {}", e)))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(token_response(tokens.access_token, token_lifetime_minutes(false), Some(tokens.refresh_token), Some(request.scope), id_token))
+}
+
+/// `POST /oauth/token` – the token endpoint.
+pub async fn token(
+    State(pool): State<PgPool>,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(req): Form<TokenRequest>,
+) -> Result<Response, OAuthError> {
+    let mut conn = pool.get().map_err(|e| OAuthError::ServerError(format!("Database connection error: {}", e)))?;
+
+    let client = identify_client(&mut conn, basic, req.client_id.clone(), req.client_secret.clone())?;
+
+    match req.grant_type.as_str() {
+        "authorization_code" => authorization_code_grant(&mut conn, &client, req),
+        "refresh_token" => refresh_token_grant(&mut conn, &client, req).await,
+        "client_credentials" => client_credentials_grant(&mut conn, &client, req),
+        "urn:ietf:params:oauth:grant-type:device_code" => device_code_grant(&mut conn, &client, req),
+        _ => Err(OAuthError::UnsupportedGrantType),
+    }
+}
+
+/// `POST /oauth/device_authorization` – starts the device authorization grant
+/// (RFC 8628) for devices that cannot open a browser redirect. The device
+/// shows the user code and polls `/oauth/token` while the user approves it at
+/// the verification URI.
+pub async fn device_authorization(
+    State(pool): State<PgPool>,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(req): Form<DeviceAuthorizationRequest>,
+) -> Result<Response, OAuthError> {
+    let mut conn = pool.get().map_err(|e| OAuthError::ServerError(format!("Database connection error: {}", e)))?;
+
+    let client = identify_client(&mut conn, basic, req.client_id, req.client_secret)?;
+    let scope = grant_scope(&client, req.scope.as_deref()).ok_or(OAuthError::InvalidScope)?;
+    if has_scope(Some(&scope), "openid") && !oidc_enabled() {
+        return Err(OAuthError::InvalidScope);
+    }
+
+    let (device_code, request) = create_device_code(&mut conn, &client.client_id, &scope)?;
+    let verification_uri = format!("{}/oauth/device", get_public_url());
+
+    Ok((
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "no-store")],
+        Json(json!({
+            "device_code": device_code,
+            "user_code": request.user_code,
+            "verification_uri_complete": format!("{}?user_code={}", verification_uri, request.user_code),
+            "verification_uri": verification_uri,
+            "expires_in": DEVICE_CODE_LIFETIME_MINUTES * 60,
+            "interval": request.poll_interval,
+        })),
+    ).into_response())
+}
+
+fn active_response(claims: &Claims, token_type: &str) -> serde_json::Value {
+    json!({
+        "active": true,
+        "token_type": token_type,
+        "sub": claims.sub,
+        "iss": claims.iss,
+        "aud": claims.aud,
+        "iat": claims.iat,
+        "nbf": claims.nbf,
+        "exp": claims.exp,
+        "jti": claims.jti,
+        "scope": claims.scope.clone().unwrap_or_else(|| claims.permissions.join(" ")),
+        "client_id": claims.client_id,
+        "org": claims.org,
+        "machine": claims.machine,
+    })
+}
+
+/// An access token is active while its signature holds and its session (or,
+/// for machine tokens, its stored digest) exists.
+fn introspect_access_token(conn: &mut PgConnection, raw_token: &str) -> Option<serde_json::Value> {
+    let token_data = decode_access_token(raw_token).ok()?;
+    if token_data.claims.machine {
+        return find_client_token(conn, raw_token)
+            .ok()?
+            .map(|_| active_response(&token_data.claims, "access_token"));
+    }
+
+    let has_session = sessions::table
+        .filter(sessions::token.eq(hash_token(raw_token)))
+        .count()
+        .get_result::<i64>(conn)
+        .ok()? > 0;
+
+    (has_session && !token_data.claims.refresh)
+        .then(|| active_response(&token_data.claims, "access_token"))
+}
+
+/// A refresh token is active until it is rotated, revoked or its session ends.
+async fn introspect_refresh_token(conn: &mut PgConnection, raw_token: &str) -> Option<serde_json::Value> {
+    let token_data = validate_refresh_token(raw_token).await.ok()?;
+    let stored = find_refresh_token(conn, raw_token).ok()??;
+    let has_session = sessions::table
+        .filter(sessions::refresh_token.eq(hash_token(raw_token)))
+        .count()
+        .get_result::<i64>(conn)
+        .ok()? > 0;
+
+    (has_session && !is_spent(&stored))
+        .then(|| active_response(&token_data.claims, "refresh_token"))
+}
+
+/// RFC 7662 token introspection for resource servers. Unknown, expired and
+/// revoked tokens are all reported as `{"active": false}`.
+pub async fn introspect(
+    State(pool): State<PgPool>,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(req): Form<IntrospectRequest>,
+) -> Result<Response, OAuthError> {
+    let mut conn = pool.get().map_err(|e| OAuthError::ServerError(format!("Database connection error: {}", e)))?;
+
+    authenticate(&mut conn, basic, req.client_id, req.client_secret)?;
+
+    // The hint only decides which kind of token is tried first
+    let introspection = if req.token_type_hint.as_deref() == Some("refresh_token") {
+        match introspect_refresh_token(&mut conn, &req.token).await {
+            Some(active) => Some(active),
+            None => introspect_access_token(&mut conn, &req.token),
+        }
+    } else {
+        match introspect_access_token(&mut conn, &req.token) {
+            Some(active) => Some(active),
+            None => introspect_refresh_token(&mut conn, &req.token).await,
+        }
+    };
+
+    Ok((
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "no-store")],
+        Json(introspection.unwrap_or_else(|| json!({ "active": false }))),
+    ).into_response())
+}
+
+/// RFC 7009 token revocation. Revoking an access or refresh token ends its
+/// session and refresh token family. Clients can only revoke tokens issued to
+/// them; public clients identify themselves by `client_id`. Unknown tokens,
+/// and other clients' tokens, are not an error, so the response is `200 OK`
+/// whenever the client is identified.
+pub async fn revoke(
+    State(pool): State<PgPool>,
+    basic: Option<TypedHeader<Authorization<Basic>>>,
+    Form(req): Form<RevokeRequest>,
+) -> Result<Response, OAuthError> {
+    let mut conn = pool.get().map_err(|e| OAuthError::ServerError(format!("Database connection error: {}", e)))?;
+
+    let client = identify_client(&mut conn, basic, req.client_id, req.client_secret)?;
+
+    invalidate_token(&mut conn, &req.token, req.token_type_hint.as_deref(), Some(&client.client_id))?;
+
+    Ok(StatusCode::OK.into_response())
+}
@@ -0,0 +1,103 @@
+// src/handlers/oauth_clients.rs
+
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use validator::Validate;
+use diesel::prelude::*;
+use crate::db::PgPool;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::OAuthClient;
+use crate::schema::oauth_clients;
+use crate::utils::error::AppError;
+use crate::utils::oauth_client::{is_public, is_valid_redirect_uri, register_client, ClientRegistration};
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct CreateClientRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    name: String,
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+    #[serde(default)]
+    allowed_scopes: Vec<String>,
+    // Public clients get no secret and must use PKCE
+    #[serde(default)]
+    public: bool,
+}
+
+/// Client as returned by the API, without the secret hash.
+fn client_response(client: &OAuthClient) -> serde_json::Value {
+    json!({
+        "client_id": client.client_id,
+        "name": client.name,
+        "redirect_uris": client.redirect_uris,
+        "allowed_scopes": client.allowed_scopes,
+        "public": is_public(client),
+        "created_by": client.created_by,
+        "created_at": client.created_at,
+    })
+}
+
+pub async fn list_clients(State(pool): State<PgPool>) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let clients: Vec<_> = oauth_clients::table
+        .order(oauth_clients::created_at.desc())
+        .load::<OAuthClient>(&mut conn)?
+        .iter()
+        .map(client_response)
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "clients": clients }))).into_response())
+}
+
+/// Registers a client. The secret is only ever returned here.
+pub async fn create_client(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<CreateClientRequest>,
+) -> Result<Response, AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    if let Some(uri) = req.redirect_uris.iter().find(|uri| !is_valid_redirect_uri(uri)) {
+        return Err(AppError::ValidationError(format!("Invalid redirect URI: {}", uri)));
+    }
+    if req.allowed_scopes.iter().any(|scope| scope.is_empty() || scope.contains(char::is_whitespace)) {
+        return Err(AppError::ValidationError("Scopes must be non-empty and contain no spaces".to_string()));
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let registration = ClientRegistration {
+        name: req.name.trim().to_string(),
+        redirect_uris: req.redirect_uris,
+        allowed_scopes: req.allowed_scopes,
+        public: req.public,
+    };
+    let (client, client_secret) = register_client(&mut conn, registration, auth.user_id)?;
+
+    let mut client_resp = client_response(&client);
+    if let Some(client_secret) = client_secret {
+        client_resp["client_secret"] = json!(client_secret);
+    }
+
+    Ok((StatusCode::CREATED, Json(client_resp)).into_response())
+}
+
+pub async fn delete_client(
+    State(pool): State<PgPool>,
+    Path(client_id): Path<String>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let deleted = diesel::delete(oauth_clients::table.find(&client_id)).execute(&mut conn)?;
+    if deleted == 0 {
+        return Err(AppError::NotFound("Client not found".to_string()));
+    }
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Client deleted" }))).into_response())
+}
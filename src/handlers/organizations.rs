@@ -0,0 +1,232 @@
+// src/handlers/organizations.rs
+
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use validator::Validate;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::db::PgPool;
+use crate::handlers::login::start_session;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{Organization, OrganizationMember, User, UserResponse};
+use crate::schema::{organization_members, organizations, sessions, users};
+use crate::utils::error::AppError;
+use crate::utils::organizations::{can_manage_members, create_organization, find_membership, ORG_ROLES};
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct CreateOrganizationRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UpdateMemberRequest {
+    role: String,
+}
+
+fn require_membership(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    auth: &AuthUser,
+) -> Result<OrganizationMember, AppError> {
+    find_membership(conn, org_id, auth.user_id)?
+        .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))
+}
+
+pub(crate) fn require_manager(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    auth: &AuthUser,
+) -> Result<OrganizationMember, AppError> {
+    let membership = require_membership(conn, org_id, auth)?;
+    if can_manage_members(&membership.role) {
+        Ok(membership)
+    } else {
+        Err(AppError::Forbidden("Insufficient permissions".to_string()))
+    }
+}
+
+fn owner_count(conn: &mut PgConnection, org_id: Uuid) -> QueryResult<i64> {
+    organization_members::table
+        .filter(organization_members::organization_id.eq(org_id))
+        .filter(organization_members::role.eq("owner"))
+        .count()
+        .get_result(conn)
+}
+
+/// Org roles live in the access token, so the member's sessions in this
+/// organization have to go when their membership changes.
+fn revoke_org_sessions(conn: &mut PgConnection, org_id: Uuid, member_id: Uuid) -> QueryResult<usize> {
+    diesel::delete(
+        sessions::table
+            .filter(sessions::user_id.eq(member_id))
+            .filter(sessions::organization_id.eq(org_id)),
+    )
+    .execute(conn)
+}
+
+pub async fn list_organizations(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let memberships = organization_members::table
+        .inner_join(organizations::table)
+        .filter(organization_members::user_id.eq(auth.user_id))
+        .order(organization_members::created_at.asc())
+        .select((Organization::as_select(), organization_members::role))
+        .load::<(Organization, String)>(&mut conn)?;
+
+    let organizations_resp: Vec<_> = memberships
+        .into_iter()
+        .map(|(organization, role)| json!({
+            "id": organization.id,
+            "name": organization.name,
+            "slug": organization.slug,
+            "role": role,
+            "active": auth.organization_id == Some(organization.id),
+        }))
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "organizations": organizations_resp }))).into_response())
+}
+
+pub async fn create_org(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<CreateOrganizationRequest>,
+) -> Result<Response, AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let organization = create_organization(&mut conn, req.name.trim(), auth.user_id)?;
+
+    Ok((StatusCode::CREATED, Json(organization)).into_response())
+}
+
+/// Replaces the current session with one acting in the given organization.
+pub async fn switch_organization(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    require_membership(&mut conn, org_id, &auth)?;
+    let user = users::table
+        .find(auth.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::Forbidden("Account no longer exists".to_string()))?;
+
+    diesel::delete(sessions::table.find(auth.session_id)).execute(&mut conn)?;
+
+    Ok(start_session(&mut conn, &user, Some(org_id), "Organization switched").await)
+}
+
+pub async fn list_members(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    require_membership(&mut conn, org_id, &auth)?;
+
+    let members = organization_members::table
+        .inner_join(users::table)
+        .filter(organization_members::organization_id.eq(org_id))
+        .filter(users::deleted_at.is_null())
+        .order(organization_members::created_at.asc())
+        .select((User::as_select(), organization_members::role))
+        .load::<(User, String)>(&mut conn)?;
+
+    let members_resp: Vec<_> = members
+        .into_iter()
+        .map(|(user, role)| json!({
+            "user": UserResponse::from(user),
+            "role": role,
+        }))
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "members": members_resp }))).into_response())
+}
+
+pub async fn update_member(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path((org_id, member_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateMemberRequest>,
+) -> Result<Response, AppError> {
+    if !ORG_ROLES.contains(&req.role.as_str()) {
+        return Err(AppError::ValidationError(format!("Invalid role: {}", req.role)));
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let actor = require_manager(&mut conn, org_id, &auth)?;
+    let member = find_membership(&mut conn, org_id, member_id)?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    // Only owners hand out or take away ownership
+    if (req.role == "owner" || member.role == "owner") && actor.role != "owner" {
+        return Err(AppError::Forbidden("Only owners can change ownership".to_string()));
+    }
+    if member.role == "owner" && req.role != "owner" && owner_count(&mut conn, org_id)? <= 1 {
+        return Err(AppError::ValidationError("An organization needs at least one owner".to_string()));
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(organization_members::table.find((org_id, member_id)))
+            .set(organization_members::role.eq(&req.role))
+            .execute(conn)?;
+        revoke_org_sessions(conn, org_id, member_id)?;
+        Ok(())
+    })?;
+
+    Ok((StatusCode::OK, Json(json!({ "user_id": member_id, "role": req.role }))).into_response())
+}
+
+/// Removes a member. Members may always remove themselves.
+pub async fn remove_member(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path((org_id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let actor = if member_id == auth.user_id {
+        require_membership(&mut conn, org_id, &auth)?
+    } else {
+        require_manager(&mut conn, org_id, &auth)?
+    };
+    let member = find_membership(&mut conn, org_id, member_id)?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    if member.role == "owner" {
+        if actor.role != "owner" {
+            return Err(AppError::Forbidden("Only owners can change ownership".to_string()));
+        }
+        if owner_count(&mut conn, org_id)? <= 1 {
+            return Err(AppError::ValidationError("An organization needs at least one owner".to_string()));
+        }
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(organization_members::table.find((org_id, member_id)))
+            .execute(conn)?;
+        revoke_org_sessions(conn, org_id, member_id)?;
+        Ok(())
+    })?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "Member removed" }))).into_response())
+}
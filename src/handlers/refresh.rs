@@ -1,61 +1,76 @@
-// src/handlers/refresh_token.rs
+// src/handlers/refresh.rs
 
-    use axum::{
-        extract::State,
-        http::StatusCode,
-        response::IntoResponse,
-        Json,
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::TypedHeader;
+use axum_extra::headers::Cookie;
+use serde::Deserialize;
+use serde_json::json;
+use crate::db::PgPool;
+use crate::utils::gen_refresh_token::refresh_tokens;
+use crate::utils::jwt::token_lifetime_minutes;
+
+#[derive(Deserialize, Debug)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// Exchanges a refresh token for a new token pair. Browsers send the token in
+/// the `refresh_token` cookie, other clients in the body; body clients get the
+/// new refresh token back in the body as well.
+pub async fn refresh_token(
+    State(pool): State<PgPool>,
+    cookie: Option<TypedHeader<Cookie>>,
+    body: Option<Json<RefreshRequest>>,
+) -> Response {
+    let from_body = body.is_some();
+    let refresh_token_str = match body
+        .map(|Json(req)| req.refresh_token)
+        .or_else(|| cookie.and_then(|c| c.get("refresh_token").map(|s| s.to_string())))
+    {
+        Some(rt) => rt,
+        None => return (StatusCode::UNAUTHORIZED, "No refresh token").into_response(),
     };
-    use axum_extra::headers::{Authorization, authorization::Bearer};
-    use diesel::prelude::*;
-    use crate::db::PgPool;
-    use crate::utils::jwt::generate_jwt;
-    use crate::models::User;
-    use serde_json::json;
-    use std::env;
-    use crate::schema::users;
-    use crate::utils::jwt_validator::validate_jwt;
 
-    pub async fn refresh_token(
-        State(pool): State<PgPool>,
-        Authorization(bearer): Authorization<Bearer>,
-    ) -> impl IntoResponse {
-        let conn = &mut pool.get().expect("Failed to get DB connection");
+    let mut conn = match pool.get() {
+        Ok(conn) => conn,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+    };
 
-        match validate_jwt(bearer.token()).await {
-            Ok(token_data) => {
-                // Check if the token is a refresh token
-                if token_data.claims.refresh {
-                    // Find the user by username
-                    match users::table
-                        .filter(users::username.eq(&token_data.claims.sub))
-                        .first::<User>(conn)
-                    {
-                        Ok(user) => {
-                            // Generate new access token
-                            match generate_jwt(user.username.clone(), &env::var("JWT_SECRET").unwrap(), false) {
-                                Ok(new_access_token) => {
-                                    let response = json!({
-                                        "message": "Token refreshed successfully",
-                                        "token": new_access_token,
-                                    });
-                                    (StatusCode::OK, Json(response)).into_response()
-                                },
-                                Err(_) => (
-                                    StatusCode::INTERNAL_SERVER_ERROR,
-                                    "Failed to generate new token".to_string()
-                                ).into_response()
-                            }
-                        },
-                        Err(_) => (
-                            StatusCode::UNAUTHORIZED,
-                            "User not found".to_string()
-                        ).into_response()
-                    }
-                } else {
-                    (StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()).into_response()
-                }
+    match refresh_tokens(&refresh_token_str, &mut conn, None).await {
+        Ok((new_access_token, new_refresh_token)) => {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {}", new_access_token)).unwrap()
+            );
+            headers.insert(
+                header::SET_COOKIE,
+                HeaderValue::from_str(&format!(
+                    "refresh_token={}; HttpOnly; Path=/; Max-Age={}",
+                    new_refresh_token,
+                    token_lifetime_minutes(true) * 60
+                )).unwrap(),
+            );
+
+            let mut refresh_resp = json!({
+                "message": "Token refreshed successfully",
+                "token": new_access_token,
+            });
+            if from_body {
+                refresh_resp["refresh_token"] = json!(new_refresh_token);
             }
-            Err(err) => (StatusCode::UNAUTHORIZED, err.to_string()).into_response(),
-        }
-    }
\ No newline at end of file
+
+            (StatusCode::OK, headers, Json(refresh_resp)).into_response()
+        },
+        Err(err) => (
+            StatusCode::UNAUTHORIZED,
+            [(header::SET_COOKIE, "refresh_token=; HttpOnly; Path=/; Max-Age=0")],
+            err,
+        ).into_response(),
+    }
+}
@@ -9,9 +9,13 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 use bcrypt::hash;
 use diesel::prelude::*;
-use crate::models::NewUser;
+use crate::models::{NewUser, User};
 use crate::schema::users::dsl::users;
 use crate::db::PgPool;
+use crate::utils::email::send_verification_email;
+use crate::utils::error::{conflict_as_validation, AppError};
+use crate::utils::organizations::create_organization;
+use crate::utils::verification_token::create_verification_token;
 
 #[derive(Deserialize, Serialize, Debug, Validate)]
 pub struct RegisterRequest {
@@ -40,14 +44,43 @@ pub async fn register(
     State(pool): State<PgPool>,
     Json(register_info): Json<RegisterRequest>,
 ) -> impl IntoResponse {
+    let mut conn = pool.get().expect("Failed to get DB connection from pool");
+    let created = conn.transaction::<_, AppError, _>(|conn| {
+        let user = create_account(conn, register_info)?;
+        // Every account starts out with an organization of its own
+        create_organization(conn, &user.username, user.id)?;
+        Ok(user)
+    });
+    let user = match created {
+        Ok(user) => user,
+        Err(AppError::ValidationError(message)) => return (StatusCode::BAD_REQUEST, message),
+        Err(AppError::InternalServerError(message)) => return (StatusCode::INTERNAL_SERVER_ERROR, message),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to register user".to_string()),
+    };
+
+    // The account exists at this point, so a failed email only means the user has to ask for a resend
+    let sent = match create_verification_token(&mut conn, &user) {
+        Ok((verification_token, token_expiration)) => {
+            send_verification_email(&user.email, &verification_token, token_expiration).await.is_ok()
+        },
+        Err(_) => false,
+    };
+
+    if sent {
+        (StatusCode::CREATED, "User registered successfully. Check your email to verify your account".to_string())
+    } else {
+        (StatusCode::CREATED, "User registered successfully, but the verification email could not be sent".to_string())
+    }
+}
+
+/// Validates the request and stores the account as `pending_verification`.
+pub(crate) fn create_account(conn: &mut PgConnection, register_info: RegisterRequest) -> Result<User, AppError> {
     if let Err(validation_errors) = register_info.validate() {
-        return (StatusCode::BAD_REQUEST, format!("Validation errors: {:?}", validation_errors));
+        return Err(AppError::ValidationError(format!("Validation errors: {:?}", validation_errors)));
     }
 
-    let password_hash = match hash(&register_info.password, bcrypt::DEFAULT_COST) {
-        Ok(hash) => hash,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password".to_string()),
-    };
+    let password_hash = hash(&register_info.password, bcrypt::DEFAULT_COST)
+        .map_err(|_| AppError::InternalServerError("Failed to hash password".to_string()))?;
 
     let new_user = NewUser {
         email: register_info.email,
@@ -55,12 +88,11 @@ pub async fn register(
         password_hash,
         full_name: None,
         role: "user".to_string(),
-        status: "active".to_string(),
+        status: "pending_verification".to_string(),
     };
 
-    let mut conn = pool.get().expect("Failed to get DB connection from pool");
-    match diesel::insert_into(users).values(&new_user).execute(&mut conn) {
-        Ok(_) => (StatusCode::CREATED, "User registered successfully".to_string()),
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to register user".to_string()),
-    }
-}
\ No newline at end of file
+    diesel::insert_into(users)
+        .values(&new_user)
+        .get_result::<User>(conn)
+        .map_err(conflict_as_validation)
+}
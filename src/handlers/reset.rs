@@ -0,0 +1,69 @@
+// src/handlers/reset.rs
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use bcrypt::hash;
+use diesel::prelude::*;
+use chrono::Utc;
+use crate::db::PgPool;
+use crate::schema::{sessions, users};
+use crate::utils::error::AppError;
+use crate::utils::reset_token::{consume_reset_token, find_reset_token};
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct ResetPasswordRequest {
+    token: String,
+    #[validate(length(min = 8, message = "Password must be at least 8 characters long"))]
+    new_password: String,
+}
+
+pub async fn reset_password(
+    State(pool): State<PgPool>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<(StatusCode, &'static str), AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let reset_token = find_reset_token(&mut conn, &req.token)?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired reset token".to_string()))?;
+
+    let password_hash = hash(&req.new_password, bcrypt::DEFAULT_COST)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
+
+    let reset = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // Burn the token first so a concurrent replay cannot also succeed
+        if !consume_reset_token(conn, &reset_token)? {
+            return Ok(false);
+        }
+
+        let updated = diesel::update(users::table.find(reset_token.user_id).filter(users::deleted_at.is_null()))
+            .set((
+                users::password_hash.eq(&password_hash),
+                users::password_changed_at.eq(Utc::now().naive_utc()),
+                users::login_attempts.eq(0),
+            ))
+            .execute(conn)?;
+        if updated == 0 {
+            // The account was deleted after the reset was requested
+            return Ok(false);
+        }
+
+        // Revoke every existing session for the user
+        diesel::delete(sessions::table.filter(sessions::user_id.eq(reset_token.user_id)))
+            .execute(conn)?;
+
+        Ok(true)
+    })?;
+
+    if !reset {
+        return Err(AppError::ValidationError("Invalid or expired reset token".to_string()));
+    }
+
+    Ok((StatusCode::OK, "Password has been reset successfully"))
+}
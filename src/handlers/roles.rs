@@ -0,0 +1,93 @@
+// src/handlers/roles.rs
+
+use axum::{
+    extract::{Extension, Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::db::PgPool;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{Permission, Role, User};
+use crate::schema::{permissions, role_permissions, roles, sessions, users};
+use crate::utils::error::AppError;
+use crate::utils::permissions::{find_role_ids, resolve_grants, set_user_roles};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserRolesRequest {
+    roles: Vec<String>,
+}
+
+/// Lists every role together with the permissions it grants.
+pub async fn list_roles(State(pool): State<PgPool>) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let all_roles = roles::table
+        .order(roles::name)
+        .load::<Role>(&mut conn)?;
+    let grants: Vec<(i32, Permission)> = role_permissions::table
+        .inner_join(permissions::table)
+        .select((role_permissions::role_id, Permission::as_select()))
+        .order(permissions::name)
+        .load(&mut conn)?;
+
+    let roles_resp: Vec<_> = all_roles
+        .into_iter()
+        .map(|role| {
+            let role_permissions: Vec<&str> = grants
+                .iter()
+                .filter(|(role_id, _)| *role_id == role.id)
+                .map(|(_, permission)| permission.name.as_str())
+                .collect();
+            json!({
+                "name": role.name,
+                "description": role.description,
+                "permissions": role_permissions,
+            })
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "roles": roles_resp }))).into_response())
+}
+
+/// Replaces a user's additional roles and signs them out so the new
+/// permissions are picked up on their next login.
+pub async fn update_user_roles(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<UserRolesRequest>,
+) -> Result<Response, AppError> {
+    auth.require_permission("roles:assign")?;
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table
+        .find(user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let role_ids = find_role_ids(&mut conn, &req.roles)?
+        .ok_or_else(|| AppError::ValidationError("Unknown role".to_string()))?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        set_user_roles(conn, user_id, &role_ids)?;
+        diesel::delete(sessions::table.filter(sessions::user_id.eq(user_id)))
+            .execute(conn)?;
+        Ok(())
+    })?;
+
+    let grants = resolve_grants(&mut conn, &user, None)?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "user_id": user.id,
+        "role": grants.role,
+        "roles": grants.roles,
+        "permissions": grants.permissions,
+    }))).into_response())
+}
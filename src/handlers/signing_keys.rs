@@ -0,0 +1,87 @@
+// src/handlers/signing_keys.rs
+
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use diesel::prelude::*;
+use crate::db::PgPool;
+use crate::models::JwtKey;
+use crate::schema::jwt_keys;
+use crate::utils::error::AppError;
+use crate::utils::signing_key::{create_key, promote_key};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CreateKeyRequest {
+    purpose: String,
+    // Defaults to the algorithm of the current signing key
+    algorithm: Option<String>,
+}
+
+/// Key metadata; the private key never leaves the database.
+fn key_response(key: &JwtKey) -> serde_json::Value {
+    json!({
+        "id": key.id,
+        "kid": key.kid,
+        "purpose": key.purpose,
+        "algorithm": key.algorithm,
+        "status": key.status,
+        "created_at": key.created_at,
+        "activated_at": key.activated_at,
+        "retire_after": key.retire_after,
+        "retired_at": key.retired_at,
+    })
+}
+
+pub async fn list_keys(State(pool): State<PgPool>) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let keys: Vec<_> = jwt_keys::table
+        .order(jwt_keys::created_at.desc())
+        .load::<JwtKey>(&mut conn)?
+        .iter()
+        .map(key_response)
+        .collect();
+
+    Ok((StatusCode::OK, Json(json!({ "keys": keys }))).into_response())
+}
+
+/// Publishes a new pending key. Promote it once verifiers have had time to
+/// fetch the updated JWKS.
+pub async fn create(
+    State(pool): State<PgPool>,
+    Json(req): Json<CreateKeyRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let algorithm = match req.algorithm {
+        Some(algorithm) => algorithm,
+        None => jwt_keys::table
+            .filter(jwt_keys::purpose.eq(&req.purpose))
+            .filter(jwt_keys::status.eq("active"))
+            .select(jwt_keys::algorithm)
+            .first::<String>(&mut conn)
+            .optional()?
+            .unwrap_or_else(|| "HS256".to_string()),
+    };
+
+    let key = create_key(&mut conn, &req.purpose, &algorithm)
+        .map_err(AppError::ValidationError)?;
+
+    Ok((StatusCode::CREATED, Json(key_response(&key))).into_response())
+}
+
+pub async fn promote(
+    State(pool): State<PgPool>,
+    Path(kid): Path<String>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let key = promote_key(&mut conn, &kid)
+        .map_err(AppError::NotFound)?;
+
+    Ok((StatusCode::OK, Json(key_response(&key))).into_response())
+}
@@ -0,0 +1,40 @@
+// src/handlers/userinfo.rs
+
+use axum::{
+    extract::{Extension, Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use diesel::prelude::*;
+use crate::db::PgPool;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::User;
+use crate::schema::users;
+use crate::utils::error::AppError;
+use crate::utils::id_token::{has_scope, user_claims};
+
+/// OpenID Connect UserInfo endpoint. Claims are released according to the
+/// scope the access token was granted.
+pub async fn userinfo(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    if auth.machine || !has_scope(auth.scope.as_deref(), "openid") {
+        return Err(AppError::Forbidden("insufficient_scope".to_string()));
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table
+        .find(auth.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::Forbidden("Account no longer exists".to_string()))?;
+
+    let mut userinfo_resp = json!(user_claims(&user, auth.scope.as_deref()));
+    userinfo_resp["sub"] = json!(user.id);
+
+    Ok((StatusCode::OK, Json(userinfo_resp)).into_response())
+}
@@ -0,0 +1,345 @@
+// src/handlers/users.rs
+
+use axum::{
+    extract::{Extension, Json, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use validator::Validate;
+use bcrypt::hash;
+use diesel::prelude::*;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::db::PgPool;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{NewUser, UpdateUser, User, UserResponse};
+use crate::schema::{organization_members, sessions, users};
+use crate::utils::email::send_verification_email;
+use crate::utils::error::{conflict_as_validation, AppError};
+use crate::utils::organizations::{add_member, can_manage_members, find_membership};
+use crate::utils::permissions::find_role_ids;
+use crate::utils::verification_token::{create_verification_token, delete_verification_tokens};
+
+const STATUSES: [&str; 4] = ["active", "inactive", "suspended", "pending_verification"];
+
+#[derive(Deserialize, Debug)]
+pub struct ListUsersQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+    status: Option<String>,
+    role: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct CreateUserRequest {
+    #[validate(length(min = 3, max = 50, message = "Username must be between 3 and 50 characters"))]
+    username: String,
+    #[validate(email(message = "Invalid email address"))]
+    email: String,
+    #[validate(length(min = 8, message = "Password must be at least 8 characters long"))]
+    password: String,
+    #[validate(length(max = 100, message = "Full name must be at most 100 characters"))]
+    full_name: Option<String>,
+    role: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
+pub struct UpdateUserRequest {
+    #[validate(length(min = 3, max = 50, message = "Username must be between 3 and 50 characters"))]
+    username: Option<String>,
+    #[validate(email(message = "Invalid email address"))]
+    email: Option<String>,
+    #[validate(length(max = 100, message = "Full name must be at most 100 characters"))]
+    full_name: Option<String>,
+    role: Option<String>,
+    status: Option<String>,
+}
+
+fn current_user(conn: &mut PgConnection, auth: &AuthUser) -> Result<User, AppError> {
+    users::table
+        .find(auth.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(conn)
+        .optional()?
+        .ok_or_else(|| AppError::Forbidden("Account no longer exists".to_string()))
+}
+
+/// The organization every listing and lookup is scoped to.
+fn active_organization(auth: &AuthUser) -> Result<Uuid, AppError> {
+    auth.organization_id
+        .ok_or_else(|| AppError::Forbidden("No active organization".to_string()))
+}
+
+/// Owners and admins of the active organization can look up its members
+/// without the global permission. Accounts are shared between organizations,
+/// so creating, changing and deleting them always needs the global one.
+fn require_org_permission(auth: &AuthUser, permission: &str) -> Result<(), AppError> {
+    if permission == "users:read" && auth.org_role.as_deref().is_some_and(can_manage_members) {
+        Ok(())
+    } else {
+        auth.require_permission(permission)
+    }
+}
+
+/// Acting on another user's account needs `permission` and the user has to
+/// be a member of the active organization; your own account needs neither.
+fn authorize_target(conn: &mut PgConnection, auth: &AuthUser, target_id: Uuid, permission: &str) -> Result<(), AppError> {
+    if auth.user_id == target_id {
+        return Ok(());
+    }
+    require_org_permission(auth, permission)?;
+
+    match find_membership(conn, active_organization(auth)?, target_id)? {
+        Some(_) => Ok(()),
+        None => Err(AppError::NotFound("User not found".to_string())),
+    }
+}
+
+fn check_role(conn: &mut PgConnection, role: &Option<String>) -> Result<(), AppError> {
+    match role {
+        Some(r) if find_role_ids(conn, std::slice::from_ref(r))?.is_none() => {
+            Err(AppError::ValidationError(format!("Invalid role: {}", r)))
+        },
+        _ => Ok(()),
+    }
+}
+
+fn check_allowed(value: &Option<String>, allowed: &[&str], field: &str) -> Result<(), AppError> {
+    match value {
+        Some(v) if !allowed.contains(&v.as_str()) => {
+            Err(AppError::ValidationError(format!("Invalid {}: {}", field, v)))
+        },
+        _ => Ok(()),
+    }
+}
+
+fn find_active_user(conn: &mut PgConnection, user_id: Uuid) -> Result<User, AppError> {
+    users::table
+        .find(user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(conn)
+        .optional()?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
+
+pub async fn list_users(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Query(params): Query<ListUsersQuery>,
+) -> Result<Response, AppError> {
+    require_org_permission(&auth, "users:read")?;
+    let org_id = active_organization(&auth)?;
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    current_user(&mut conn, &auth)?;
+
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(20).clamp(1, 100);
+
+    let members = || {
+        organization_members::table
+            .filter(organization_members::organization_id.eq(org_id))
+            .select(organization_members::user_id)
+    };
+    let mut query = users::table
+        .filter(users::deleted_at.is_null())
+        .filter(users::id.eq_any(members()))
+        .into_boxed();
+    let mut count_query = users::table
+        .filter(users::deleted_at.is_null())
+        .filter(users::id.eq_any(members()))
+        .into_boxed();
+    if let Some(status) = &params.status {
+        query = query.filter(users::status.eq(status));
+        count_query = count_query.filter(users::status.eq(status));
+    }
+    if let Some(role) = &params.role {
+        query = query.filter(users::role.eq(role));
+        count_query = count_query.filter(users::role.eq(role));
+    }
+
+    let total: i64 = count_query.count().get_result(&mut conn)?;
+    let results = query
+        .order(users::created_at.desc())
+        .limit(per_page)
+        .offset((page - 1) * per_page)
+        .load::<User>(&mut conn)?;
+
+    let users_resp: Vec<UserResponse> = results.into_iter().map(UserResponse::from).collect();
+
+    Ok((StatusCode::OK, Json(json!({
+        "users": users_resp,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+    }))).into_response())
+}
+
+pub async fn create_user(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<CreateUserRequest>,
+) -> Result<Response, AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    require_org_permission(&auth, "users:write")?;
+    let org_id = active_organization(&auth)?;
+    if req.role.is_some() {
+        auth.require_permission("roles:assign")?;
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    current_user(&mut conn, &auth)?;
+    check_role(&mut conn, &req.role)?;
+
+    let password_hash = hash(&req.password, bcrypt::DEFAULT_COST)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
+
+    let new_user = NewUser {
+        username: req.username,
+        email: req.email,
+        password_hash,
+        full_name: req.full_name,
+        role: req.role.unwrap_or_else(|| "user".to_string()),
+        status: "pending_verification".to_string(),
+    };
+
+    let (user, verification_token, token_expiration) = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let user = diesel::insert_into(users::table)
+            .values(&new_user)
+            .get_result::<User>(conn)?;
+        add_member(conn, org_id, user.id, "member")?;
+
+        // New accounts verify their email the same way as self-registered ones
+        let (verification_token, token_expiration) = create_verification_token(conn, &user)?;
+        Ok((user, verification_token, token_expiration))
+    })
+    .map_err(conflict_as_validation)?;
+
+    if send_verification_email(&user.email, &verification_token, token_expiration).await.is_err() {
+        log::warn!("Failed to send verification email to new user {}", user.id);
+    }
+
+    Ok((StatusCode::CREATED, Json(UserResponse::from(user))).into_response())
+}
+
+pub async fn get_user(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    current_user(&mut conn, &auth)?;
+    authorize_target(&mut conn, &auth, user_id, "users:read")?;
+
+    let user = find_active_user(&mut conn, user_id)?;
+
+    Ok((StatusCode::OK, Json(UserResponse::from(user))).into_response())
+}
+
+pub async fn update_user(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<UpdateUserRequest>,
+) -> Result<Response, AppError> {
+    req.validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+    check_allowed(&req.status, &STATUSES, "status")?;
+    if req.role.is_some() {
+        auth.require_permission("roles:assign")?;
+    }
+    if req.status.is_some() {
+        auth.require_permission("users:write")?;
+    }
+
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    current_user(&mut conn, &auth)?;
+    authorize_target(&mut conn, &auth, user_id, "users:write")?;
+    check_role(&mut conn, &req.role)?;
+
+    let existing = find_active_user(&mut conn, user_id)?;
+    let email_changed = req.email.as_ref().is_some_and(|e| !e.eq_ignore_ascii_case(&existing.email));
+    let role_changed = req.role.as_ref().is_some_and(|r| *r != existing.role);
+    let status_changed = req.status.as_ref().is_some_and(|st| *st != existing.status);
+
+    let changes = UpdateUser {
+        username: req.username,
+        email: req.email,
+        full_name: req.full_name,
+        role: req.role,
+        status: req.status,
+    };
+
+    let (user, verification) = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        if email_changed {
+            // A new address has to be verified again
+            diesel::update(users::table.find(user_id))
+                .set(users::email_verified_at.eq(None::<chrono::NaiveDateTime>))
+                .execute(conn)?;
+        }
+        if role_changed || status_changed {
+            // Access tokens carry the role, and a suspended account must lose
+            // access right away, so make the user log in again
+            diesel::delete(sessions::table.filter(sessions::user_id.eq(user_id)))
+                .execute(conn)?;
+        }
+
+        let user = diesel::update(users::table.find(user_id))
+            .set((&changes, users::updated_at.eq(Utc::now().naive_utc())))
+            .get_result::<User>(conn)?;
+
+        // Links sent to the old address must not verify the new one
+        let verification = if email_changed {
+            delete_verification_tokens(conn, user_id)?;
+            Some(create_verification_token(conn, &user)?)
+        } else {
+            None
+        };
+
+        Ok((user, verification))
+    })
+    .map_err(conflict_as_validation)?;
+
+    if let Some((verification_token, token_expiration)) = verification {
+        if send_verification_email(&user.email, &verification_token, token_expiration).await.is_err() {
+            log::warn!("Failed to send verification email to user {}", user.id);
+        }
+    }
+
+    Ok((StatusCode::OK, Json(UserResponse::from(user))).into_response())
+}
+
+/// Soft-deletes the user and revokes all of their sessions.
+pub async fn delete_user(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    current_user(&mut conn, &auth)?;
+    authorize_target(&mut conn, &auth, user_id, "users:delete")?;
+    find_active_user(&mut conn, user_id)?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(users::table.find(user_id))
+            .set((
+                users::deleted_at.eq(Utc::now().naive_utc()),
+                users::status.eq("inactive"),
+            ))
+            .execute(conn)?;
+
+        diesel::delete(sessions::table.filter(sessions::user_id.eq(user_id)))
+            .execute(conn)?;
+
+        Ok(())
+    })?;
+
+    Ok((StatusCode::OK, Json(json!({ "message": "User deleted" }))).into_response())
+}
@@ -0,0 +1,95 @@
+// src/handlers/verify.rs
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use diesel::prelude::*;
+use chrono::Utc;
+use crate::db::PgPool;
+use crate::models::User;
+use crate::schema::users;
+use crate::utils::email::send_verification_email;
+use crate::utils::error::AppError;
+use crate::utils::verification_token::{consume_verification_token, create_verification_token, find_verification_token};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct VerifyEmailRequest {
+    token: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ResendVerificationRequest {
+    email: String,
+}
+
+pub async fn verify_email(
+    State(pool): State<PgPool>,
+    Json(req): Json<VerifyEmailRequest>,
+) -> Result<(StatusCode, &'static str), AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let verification_token = find_verification_token(&mut conn, &req.token)?
+        .ok_or_else(|| AppError::ValidationError("Invalid or expired verification token".to_string()))?;
+
+    let verified = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        if !consume_verification_token(conn, &verification_token)? {
+            return Ok(false);
+        }
+
+        // The token only vouches for the address it was sent to
+        let verified = diesel::update(
+            users::table
+                .find(verification_token.user_id)
+                .filter(users::email.eq(&verification_token.email)),
+        )
+        .set(users::email_verified_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+        if verified == 0 {
+            return Ok(false);
+        }
+
+        // Only pending accounts are activated; suspended accounts stay suspended
+        diesel::update(
+            users::table
+                .find(verification_token.user_id)
+                .filter(users::status.eq("pending_verification")),
+        )
+        .set(users::status.eq("active"))
+        .execute(conn)?;
+
+        Ok(true)
+    })?;
+
+    if !verified {
+        return Err(AppError::ValidationError("Invalid or expired verification token".to_string()));
+    }
+
+    Ok((StatusCode::OK, "Email verified successfully"))
+}
+
+pub async fn resend_verification(
+    State(pool): State<PgPool>,
+    Json(req): Json<ResendVerificationRequest>,
+) -> Result<(StatusCode, &'static str), AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table
+        .filter(users::email.eq(&req.email))
+        .filter(users::email_verified_at.is_null())
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?;
+
+    if let Some(user) = user {
+        let (verification_token, token_expiration) = create_verification_token(&mut conn, &user)?;
+
+        send_verification_email(&user.email, &verification_token, token_expiration)
+            .await
+            .map_err(|e| AppError::EmailError(format!("Failed to send verification email: {}", e)))?;
+    }
+
+    // Don't reveal if the user exists or is already verified
+    Ok((StatusCode::OK, "If the account exists and is unverified, a verification email has been sent"))
+}
@@ -0,0 +1,223 @@
+// src/handlers/webauthn.rs
+
+use axum::{
+    extract::{Extension, Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use diesel::prelude::*;
+use chrono::Utc;
+use crate::db::PgPool;
+use crate::handlers::login::successful_login;
+use crate::middleware::token_validator::AuthUser;
+use crate::models::{NewWebauthnCredential, User, WebauthnCredential};
+use crate::schema::{users, webauthn_credentials};
+use crate::utils::error::AppError;
+use crate::utils::webauthn::{
+    check_sign_count, create_challenge, decode_b64url, encode_b64url, parse_attestation_object, parse_authenticator_data,
+    parse_client_data, rp_id, rp_name, take_challenge, verify_assertion_signature, COSE_ALG_ES256,
+};
+
+#[derive(Deserialize, Debug)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    attestation_object: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FinishRegistrationRequest {
+    id: String,
+    response: AttestationResponse,
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StartLoginRequest {
+    username: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    authenticator_data: String,
+    signature: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FinishLoginRequest {
+    id: String,
+    response: AssertionResponse,
+}
+
+fn credential_descriptors(credentials: &[WebauthnCredential]) -> Vec<serde_json::Value> {
+    credentials
+        .iter()
+        .map(|c| json!({ "type": "public-key", "id": c.credential_id }))
+        .collect()
+}
+
+/// Returns `PublicKeyCredentialCreationOptions` for registering a new passkey.
+pub async fn start_registration(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = users::table.find(auth.user_id).first::<User>(&mut conn)?;
+    let existing = webauthn_credentials::table
+        .filter(webauthn_credentials::user_id.eq(user.id))
+        .load::<WebauthnCredential>(&mut conn)?;
+
+    let challenge = create_challenge(&mut conn, Some(user.id), "registration")?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "publicKey": {
+            "challenge": challenge,
+            "rp": { "id": rp_id(), "name": rp_name() },
+            "user": {
+                "id": encode_b64url(user.id.as_bytes()),
+                "name": user.username,
+                "displayName": user.full_name.unwrap_or_else(|| user.username.clone()),
+            },
+            "pubKeyCredParams": [{ "type": "public-key", "alg": COSE_ALG_ES256 }],
+            "timeout": 300000,
+            "attestation": "none",
+            "excludeCredentials": credential_descriptors(&existing),
+            "authenticatorSelection": {
+                "residentKey": "preferred",
+                "userVerification": "required",
+            },
+        }
+    }))).into_response())
+}
+
+/// Verifies the attestation response and stores the new credential.
+pub async fn finish_registration(
+    State(pool): State<PgPool>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<FinishRegistrationRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let client_data_json = decode_b64url(&req.response.client_data_json)?;
+    let client_data = parse_client_data(&client_data_json, "webauthn.create")?;
+
+    let challenge = take_challenge(&mut conn, &client_data.challenge, "registration")?;
+    if challenge.user_id != Some(auth.user_id) {
+        return Err(AppError::ValidationError("Invalid or expired WebAuthn challenge".to_string()));
+    }
+
+    let auth_data = parse_authenticator_data(&parse_attestation_object(&decode_b64url(&req.response.attestation_object)?)?)?;
+    let attested = auth_data.attested_credential
+        .ok_or_else(|| AppError::ValidationError("Missing attested credential data".to_string()))?;
+
+    let credential_id = encode_b64url(&attested.credential_id);
+    if credential_id != req.id.trim_end_matches('=') {
+        return Err(AppError::ValidationError("Credential id mismatch".to_string()));
+    }
+
+    diesel::insert_into(webauthn_credentials::table)
+        .values(&NewWebauthnCredential {
+            user_id: auth.user_id,
+            credential_id,
+            public_key: attested.public_key,
+            sign_count: auth_data.sign_count as i64,
+            name: req.name,
+        })
+        .execute(&mut conn)?;
+
+    Ok((StatusCode::CREATED, Json(json!({ "message": "Passkey registered" }))).into_response())
+}
+
+/// Returns `PublicKeyCredentialRequestOptions`. Without a username the client
+/// can offer any discoverable credential for this relying party.
+pub async fn start_login(
+    State(pool): State<PgPool>,
+    Json(req): Json<StartLoginRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let user = match &req.username {
+        Some(user_name) => users::table
+            .filter(users::username.eq(user_name))
+            .filter(users::deleted_at.is_null())
+            .first::<User>(&mut conn)
+            .optional()?,
+        None => None,
+    };
+
+    let allowed = match &user {
+        Some(user) => webauthn_credentials::table
+            .filter(webauthn_credentials::user_id.eq(user.id))
+            .load::<WebauthnCredential>(&mut conn)?,
+        None => Vec::new(),
+    };
+
+    let challenge = create_challenge(&mut conn, user.map(|u| u.id), "authentication")?;
+
+    Ok((StatusCode::OK, Json(json!({
+        "publicKey": {
+            "challenge": challenge,
+            "rpId": rp_id(),
+            "timeout": 300000,
+            "allowCredentials": credential_descriptors(&allowed),
+            "userVerification": "required",
+        }
+    }))).into_response())
+}
+
+/// Verifies an assertion and issues the same session as a password login.
+pub async fn finish_login(
+    State(pool): State<PgPool>,
+    Json(req): Json<FinishLoginRequest>,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().map_err(|e| AppError::InternalServerError(format!("Database connection error: {}", e)))?;
+
+    let client_data_json = decode_b64url(&req.response.client_data_json)?;
+    let client_data = parse_client_data(&client_data_json, "webauthn.get")?;
+    let challenge = take_challenge(&mut conn, &client_data.challenge, "authentication")?;
+
+    let credential = webauthn_credentials::table
+        .filter(webauthn_credentials::credential_id.eq(req.id.trim_end_matches('=')))
+        .first::<WebauthnCredential>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::ValidationError("Unknown credential".to_string()))?;
+
+    if challenge.user_id.is_some_and(|owner| owner != credential.user_id) {
+        return Err(AppError::ValidationError("Unknown credential".to_string()));
+    }
+
+    let authenticator_data = decode_b64url(&req.response.authenticator_data)?;
+    let auth_data = parse_authenticator_data(&authenticator_data)?;
+    verify_assertion_signature(
+        &credential.public_key,
+        &authenticator_data,
+        &client_data_json,
+        &decode_b64url(&req.response.signature)?,
+    )?;
+
+    check_sign_count(credential.sign_count, auth_data.sign_count)?;
+    let sign_count = auth_data.sign_count as i64;
+
+    diesel::update(webauthn_credentials::table.find(credential.id))
+        .set((
+            webauthn_credentials::sign_count.eq(sign_count),
+            webauthn_credentials::last_used_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(&mut conn)?;
+
+    let user = users::table
+        .find(credential.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(&mut conn)
+        .optional()?
+        .ok_or_else(|| AppError::ValidationError("Unknown credential".to_string()))?;
+
+    Ok(successful_login(&mut conn, &user).await)
+}
@@ -0,0 +1,54 @@
+// src/handlers/well_known.rs
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use crate::config::{get_jwt_issuer, get_public_url};
+use crate::utils::error::AppError;
+use crate::utils::id_token::oidc_enabled;
+use crate::utils::signing_key::{jwks as key_set, signing_key, JWKS_MAX_AGE_SECONDS};
+
+/// Public keys for verifying access tokens, so other services can do it offline.
+pub async fn jwks() -> Response {
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, format!("public, max-age={}", JWKS_MAX_AGE_SECONDS))],
+        Json(key_set()),
+    ).into_response()
+}
+
+/// OpenID Connect discovery document. Not found while OpenID Connect is
+/// unavailable, i.e. access tokens are signed with HS256.
+pub async fn openid_configuration() -> Response {
+    let algorithm = match signing_key(false) {
+        Ok(key) if oidc_enabled() => format!("{:?}", key.algorithm),
+        _ => return AppError::NotFound("OpenID Connect is not enabled".to_string()).into_response(),
+    };
+    let base = get_public_url();
+
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, format!("public, max-age={}", JWKS_MAX_AGE_SECONDS))],
+        Json(json!({
+            "issuer": get_jwt_issuer(),
+            "authorization_endpoint": format!("{}/oauth/authorize", base),
+            "token_endpoint": format!("{}/oauth/token", base),
+            "userinfo_endpoint": format!("{}/userinfo", base),
+            "jwks_uri": format!("{}/.well-known/jwks.json", base),
+            "introspection_endpoint": format!("{}/oauth/introspect", base),
+            "revocation_endpoint": format!("{}/oauth/revoke", base),
+            "device_authorization_endpoint": format!("{}/oauth/device_authorization", base),
+            "response_types_supported": ["code"],
+            "grant_types_supported": ["authorization_code", "refresh_token", "client_credentials", "urn:ietf:params:oauth:grant-type:device_code"],
+            "subject_types_supported": ["public"],
+            "id_token_signing_alg_values_supported": [algorithm],
+            "scopes_supported": ["openid", "profile", "email"],
+            "claims_supported": ["iss", "sub", "aud", "exp", "iat", "auth_time", "nonce", "email", "email_verified", "preferred_username", "name"],
+            "token_endpoint_auth_methods_supported": ["client_secret_basic", "client_secret_post", "none"],
+            "code_challenge_methods_supported": ["S256"],
+        })),
+    ).into_response()
+}
@@ -21,6 +21,10 @@ async fn main() {
     // Initialize the database connection pool
     let pool = establish_connection_pool();
 
+    // Signing keys are loaded once here and refreshed in the background
+    utils::signing_key::load_keys(&pool).expect("Failed to load signing keys");
+    tokio::spawn(utils::signing_key::run_key_schedule(pool.clone(), config::get_key_rotation_days()));
+
     // Create router with routes and await it immediately
     let app = routes::create_routes(pool).await;
     // Run the server
@@ -1 +1,3 @@
-pub mod token_validator;
\ No newline at end of file
+pub mod token_validator;
+pub mod require_role;
+pub mod require_first_party;
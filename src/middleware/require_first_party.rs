@@ -0,0 +1,28 @@
+// src/middleware/require_first_party.rs
+
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::middleware::token_validator::AuthUser;
+use crate::utils::error::AppError;
+
+/// Keeps tokens issued to OAuth clients off routes that manage the account
+/// itself (credentials, MFA, organizations, other users). This includes
+/// `client_credentials` tokens, which have no account at all. A client only
+/// ever acts through the permissions its scope delegates, on the routes that
+/// check them.
+/// Attach after `auth_middleware`: `.route_layer(from_fn(require_first_party))`
+pub async fn require_first_party(req: Request, next: Next) -> Response {
+    let allowed = req
+        .extensions()
+        .get::<AuthUser>()
+        .is_some_and(|auth| auth.client_id.is_none());
+
+    if allowed {
+        next.run(req).await
+    } else {
+        AppError::Forbidden("Not available to OAuth clients".to_string()).into_response()
+    }
+}
@@ -0,0 +1,32 @@
+// src/middleware/require_role.rs
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use crate::middleware::token_validator::AuthUser;
+use crate::utils::error::AppError;
+
+/// Role a route requires, checked against the roles carried in the access token.
+/// Attach after `auth_middleware`:
+/// `.route_layer(from_fn_with_state(RequireRole("admin"), require_role))`
+#[derive(Clone, Copy, Debug)]
+pub struct RequireRole(pub &'static str);
+
+pub async fn require_role(
+    State(RequireRole(role)): State<RequireRole>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let allowed = req
+        .extensions()
+        .get::<AuthUser>()
+        .is_some_and(|auth| auth.has_role(role));
+
+    if allowed {
+        next.run(req).await
+    } else {
+        AppError::Forbidden("Insufficient permissions".to_string()).into_response()
+    }
+}
@@ -11,24 +11,122 @@ use axum_extra::TypedHeader;
 use axum_extra::headers::{Authorization, Cookie};
 use axum_extra::headers::authorization::Bearer;
 use diesel::prelude::*;
+use uuid::Uuid;
 use crate::db::PgPool;
-use crate::models::Session;
+use crate::models::{OAuthClientToken, Session};
 use crate::schema::sessions::dsl::sessions;
 use crate::schema::sessions::{refresh_token, token};
-use crate::utils::jwt_validator::validate_jwt;
-use crate::utils::gen_refresh_token::refresh_tokens;
+use crate::utils::jwt::Claims;
+use crate::utils::jwt_validator::decode_access_token;
+use crate::utils::error::AppError;
+use crate::utils::secure_token::hash_token;
+use crate::utils::gen_refresh_token::{detect_reuse, refresh_tokens};
+use crate::utils::oauth_client::find_client_token;
 
-pub async fn auth_middleware<T>(
+/// The user behind the session that authenticated the current request, or
+/// the OAuth client behind a `client_credentials` token.
+#[derive(Clone, Debug)]
+pub struct AuthUser {
+    /// Nil for machine principals, so it matches no account
+    pub user_id: Uuid,
+    /// Zero for machine principals, so it matches no session
+    pub session_id: i32,
+    pub role: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    pub organization_id: Option<Uuid>,
+    pub org_role: Option<String>,
+    /// Scope granted to the OAuth client the token was issued to
+    pub scope: Option<String>,
+    /// OAuth client the token was issued to; `None` for first-party sessions
+    pub client_id: Option<String>,
+    /// The client acts on its own behalf, without a user
+    pub machine: bool,
+}
+
+impl AuthUser {
+    fn new(session: &Session, claims: Claims) -> Self {
+        AuthUser {
+            user_id: session.user_id,
+            session_id: session.id,
+            role: claims.role,
+            roles: claims.roles,
+            permissions: claims.permissions,
+            organization_id: claims.org,
+            org_role: claims.org_role,
+            scope: claims.scope,
+            client_id: claims.client_id,
+            machine: false,
+        }
+    }
+
+    fn client(stored: &OAuthClientToken, claims: Claims) -> Self {
+        AuthUser {
+            user_id: Uuid::nil(),
+            session_id: 0,
+            role: claims.role,
+            roles: claims.roles,
+            permissions: claims.permissions,
+            organization_id: None,
+            org_role: None,
+            scope: Some(stored.scope.clone()),
+            client_id: Some(stored.client_id.clone()),
+            machine: true,
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.role == role || self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    /// Guard for handlers: `auth.require_permission("users:read")?;`
+    pub fn require_permission(&self, permission: &str) -> Result<(), AppError> {
+        if self.has_permission(permission) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden("Insufficient permissions".to_string()))
+        }
+    }
+}
+
+pub async fn auth_middleware(
     TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
     cookie: Option<TypedHeader<Cookie>>,
     State(pool): State<PgPool>,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> impl IntoResponse {
     let access_token = bearer.token();
-    println!("Access token is here {:?}", access_token);
-    match validate_jwt(access_token).await {
-        Ok(_) => next.run(req).await,
+    // Signature and expiry only; the session or client token is looked up below
+    match decode_access_token(access_token) {
+        Ok(token_data) => {
+            let mut conn = match pool.get() {
+                Ok(conn) => conn,
+                Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response(),
+            };
+
+            if token_data.claims.machine {
+                return match find_client_token(&mut conn, access_token) {
+                    Ok(Some(stored)) if token_data.claims.sub == stored.client_id => {
+                        req.extensions_mut().insert(AuthUser::client(&stored, token_data.claims));
+                        next.run(req).await
+                    },
+                    _ => (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
+                };
+            }
+
+            match sessions.filter(token.eq(hash_token(access_token))).first::<Session>(&mut conn) {
+                Ok(session) if token_data.claims.user_id() == Some(session.user_id) => {
+                    req.extensions_mut().insert(AuthUser::new(&session, token_data.claims));
+                    next.run(req).await
+                },
+                _ => (StatusCode::UNAUTHORIZED, "Invalid session").into_response(),
+            }
+        },
         Err(err) if err == "Token has expired" => {
             let mut conn = match pool.get() {
                 Ok(conn) => conn,
@@ -42,22 +140,30 @@ pub async fn auth_middleware<T>(
             };
 
             // 2. Verify session exists with this access token and refresh token pair
-            let _session = match sessions
-                .filter(token.eq(access_token))
-                .filter(refresh_token.eq(&refresh_token_str))
+            let session = match sessions
+                .filter(token.eq(hash_token(access_token)))
+                .filter(refresh_token.eq(hash_token(&refresh_token_str)))
                 .first::<Session>(&mut conn) {
                 Ok(s) => s,
-                Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid session").into_response(),
+                Err(_) => {
+                    // A refresh token that was already rotated away has been replayed
+                    if detect_reuse(&mut conn, &refresh_token_str) {
+                        return (StatusCode::UNAUTHORIZED, "Refresh token reuse detected").into_response();
+                    }
+                    return (StatusCode::UNAUTHORIZED, "Invalid session").into_response();
+                },
             };
-            println!("Refresh tokens");
             // 3. Try to refresh tokens
-            match refresh_tokens(&refresh_token_str, &mut conn).await {
+            match refresh_tokens(&refresh_token_str, &mut conn, None).await {
                 Ok((new_access_token, new_refresh_token)) => {
-                    println!("Refresh tokens are here {:?},  {:?}", new_access_token, new_refresh_token);
+                    let claims = match decode_access_token(&new_access_token) {
+                        Ok(token_data) => token_data.claims,
+                        Err(e) => return (StatusCode::UNAUTHORIZED, e).into_response(),
+                    };
                     let mut headers = HeaderMap::new();
                     headers.insert(
                         header::AUTHORIZATION,
-                        HeaderValue::from_str(&format!("{}", new_access_token)).unwrap()
+                        HeaderValue::from_str(&new_access_token).unwrap()
                     );
                     headers.insert(
                         header::SET_COOKIE,
@@ -70,6 +176,7 @@ pub async fn auth_middleware<T>(
 
                     // Create new request with new access token
                     let mut new_req = req;
+                    new_req.extensions_mut().insert(AuthUser::new(&session, claims));
                     new_req.headers_mut().insert(
                         header::AUTHORIZATION,
                         HeaderValue::from_str(&format!("Bearer {}", new_access_token)).unwrap()
@@ -79,7 +186,7 @@ pub async fn auth_middleware<T>(
                     response.headers_mut().extend(headers);
                     response
                 },
-                Err(e) => {println!("This Error");(StatusCode::UNAUTHORIZED, e).into_response()},
+                Err(e) => (StatusCode::UNAUTHORIZED, e).into_response(),
 
             }
         },
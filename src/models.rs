@@ -20,7 +20,9 @@ pub struct User {
     pub password_changed_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
-    pub deleted_at: Option<NaiveDateTime>
+    pub deleted_at: Option<NaiveDateTime>,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub mfa_type: Option<String>
 }
 
 #[derive(Insertable)]
@@ -34,6 +36,50 @@ pub struct NewUser {
     pub status: String,
 }
 
+#[derive(AsChangeset)]
+#[diesel(table_name = crate::schema::users)]
+pub struct UpdateUser {
+    pub username: Option<String>,
+    pub email: Option<String>,
+    pub full_name: Option<String>,
+    pub role: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Public view of a user; never includes the password hash or MFA secrets.
+#[derive(Debug, Serialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub full_name: Option<String>,
+    pub role: String,
+    pub status: String,
+    pub email_verified: bool,
+    pub mfa_enabled: bool,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        UserResponse {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            full_name: user.full_name,
+            role: user.role,
+            status: user.status,
+            email_verified: user.email_verified_at.is_some(),
+            mfa_enabled: user.mfa_type.is_some(),
+            last_login_at: user.last_login_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
 #[diesel(table_name = crate::schema::sessions)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -43,7 +89,11 @@ pub struct Session {
     pub token: String,
     pub refresh_token: String,
     pub expires_at: NaiveDateTime,
-    pub created_at: Option<NaiveDateTime>
+    pub created_at: Option<NaiveDateTime>,
+    pub organization_id: Option<Uuid>,
+    pub family_id: Uuid,
+    pub client_id: Option<String>,
+    pub scope: Option<String>
 }
 
 #[derive(Insertable)]
@@ -53,4 +103,426 @@ pub struct NewSession {
     pub token: String,
     pub refresh_token: String,
     pub expires_at: NaiveDateTime,
-}
\ No newline at end of file
+    pub organization_id: Option<Uuid>,
+    pub family_id: Uuid,
+    pub client_id: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::password_reset_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PasswordResetToken {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub requested_ip: Option<String>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::password_reset_tokens)]
+pub struct NewPasswordResetToken {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub requested_ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::email_verification_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct EmailVerificationToken {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub email: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::email_verification_tokens)]
+pub struct NewEmailVerificationToken {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::totp_secrets)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TotpSecret {
+    pub user_id: Uuid,
+    pub secret: String,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::totp_secrets)]
+pub struct NewTotpSecret {
+    pub user_id: Uuid,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::mfa_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MfaChallenge {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub attempts: i16,
+    pub expires_at: NaiveDateTime,
+    pub consumed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub code_hash: Option<String>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::mfa_challenges)]
+pub struct NewMfaChallenge {
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub code_hash: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::mfa_recovery_codes)]
+pub struct NewMfaRecoveryCode {
+    pub user_id: Uuid,
+    pub code_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::webauthn_credentials)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebauthnCredential {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+    pub name: Option<String>,
+    pub created_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::webauthn_credentials)]
+pub struct NewWebauthnCredential {
+    pub user_id: Uuid,
+    pub credential_id: String,
+    pub public_key: Vec<u8>,
+    pub sign_count: i64,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::webauthn_challenges)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebauthnChallenge {
+    pub id: i32,
+    pub user_id: Option<Uuid>,
+    pub challenge: String,
+    pub ceremony: String,
+    pub expires_at: NaiveDateTime,
+    pub consumed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::webauthn_challenges)]
+pub struct NewWebauthnChallenge {
+    pub user_id: Option<Uuid>,
+    pub challenge: String,
+    pub ceremony: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::magic_link_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MagicLinkToken {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub requested_ip: Option<String>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::magic_link_tokens)]
+pub struct NewMagicLinkToken {
+    pub user_id: Uuid,
+    pub email: String,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub requested_ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::roles)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Role {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::permissions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Permission {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::user_roles)]
+pub struct NewUserRole {
+    pub user_id: Uuid,
+    pub role_id: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::organizations)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::organizations)]
+pub struct NewOrganization {
+    pub name: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::organization_members)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OrganizationMember {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::organization_members)]
+pub struct NewOrganizationMember {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub role: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::organization_invitations)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OrganizationInvitation {
+    pub id: i32,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::organization_invitations)]
+pub struct NewOrganizationInvitation {
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: String,
+    pub token_hash: String,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::jwt_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JwtKey {
+    pub id: i32,
+    pub kid: String,
+    pub purpose: String,
+    pub algorithm: String,
+    pub private_key: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+    pub retire_after: Option<NaiveDateTime>,
+    pub retired_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::jwt_keys)]
+pub struct NewJwtKey {
+    pub kid: String,
+    pub purpose: String,
+    pub algorithm: String,
+    pub private_key: String,
+    pub status: String,
+    pub activated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::refresh_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RefreshToken {
+    pub id: i32,
+    pub family_id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub created_at: NaiveDateTime,
+    pub rotated_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::refresh_tokens)]
+pub struct NewRefreshToken {
+    pub family_id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::security_events)]
+pub struct NewSecurityEvent {
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::oauth_clients)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub redirect_uris: Vec<String>,
+    pub allowed_scopes: Vec<String>
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_clients)]
+pub struct NewOAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub created_by: Option<Uuid>,
+    pub redirect_uris: Vec<String>,
+    pub allowed_scopes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::oauth_authorization_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthAuthorizationCode {
+    pub id: i32,
+    pub code_hash: String,
+    pub client_id: String,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub family_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub nonce: Option<String>,
+    pub auth_time: NaiveDateTime,
+    pub redirect_uri_supplied: bool
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_authorization_codes)]
+pub struct NewOAuthAuthorizationCode {
+    pub code_hash: String,
+    pub client_id: String,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+    pub nonce: Option<String>,
+    pub auth_time: NaiveDateTime,
+    pub redirect_uri_supplied: bool,
+}
+
+#[derive(Debug, Serialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::oauth_client_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthClientToken {
+    pub id: i32,
+    pub client_id: String,
+    pub token_hash: String,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_client_tokens)]
+pub struct NewOAuthClientToken {
+    pub client_id: String,
+    pub token_hash: String,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::oauth_device_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OAuthDeviceCode {
+    pub id: i32,
+    pub device_code_hash: String,
+    pub user_code: String,
+    pub client_id: String,
+    pub scope: String,
+    pub user_id: Option<Uuid>,
+    pub poll_interval: i32,
+    pub last_polled_at: Option<NaiveDateTime>,
+    pub approved_at: Option<NaiveDateTime>,
+    pub denied_at: Option<NaiveDateTime>,
+    pub auth_time: Option<NaiveDateTime>,
+    pub redeemed_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_device_codes)]
+pub struct NewOAuthDeviceCode {
+    pub device_code_hash: String,
+    pub user_code: String,
+    pub client_id: String,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+}
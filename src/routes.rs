@@ -1,14 +1,15 @@
 // src/routes.rs
 
 use axum::{
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
-    body::Body,
-    middleware::from_fn_with_state,
+    middleware::{from_fn, from_fn_with_state},
 };
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use crate::handlers;
+use crate::middleware::require_first_party::require_first_party;
+use crate::middleware::require_role::{require_role, RequireRole};
 use crate::middleware::token_validator::auth_middleware;
 use crate::db::PgPool;
 
@@ -28,11 +29,11 @@ pub async fn root() -> impl IntoResponse {
     │  🚀 API ROUTES:                                              │
     │                                                              │
     │  📌 USERS                                                    │
-    │     GET    /users          List all users                    │
-    │     POST   /users          Create new user                   │
-    │     GET    /users/{id}     Get user details                  │
-    │     PUT    /users/{id}     Update user                       │
-    │     DELETE /users/{id}     Delete user                       │
+    │     GET    /api/users      List all users                    │
+    │     POST   /api/users      Create new user                   │
+    │     GET    /api/users/{id} Get user details                  │
+    │     PUT    /api/users/{id} Update user                       │
+    │     DELETE /api/users/{id} Delete user                       │
     │                                                              │
     │                                                              │
     ├──────────────────────────────────────────────────────────────┤
@@ -58,17 +59,112 @@ pub async fn create_routes(pool: PgPool) -> Router {
 
     let login_routes = Router::new()
         .route("/login", post(handlers::login::login))
+        .route("/login/mfa", post(handlers::mfa::login_mfa))
+        .route("/login/magic-link", post(handlers::magic_link::request_magic_link))
+        .route("/login/magic-link/callback", post(handlers::magic_link::magic_link_callback))
+        .route("/login/webauthn/start", post(handlers::webauthn::start_login))
+        .route("/login/webauthn/finish", post(handlers::webauthn::finish_login))
         .route("/register", post(handlers::register::register))
-        .route("/forgot", post(handlers::forgot::forgot_password));
+        .route("/forgot", post(handlers::forgot::forgot_password))
+        .route("/reset-password", post(handlers::reset::reset_password))
+        .route("/verify-email", post(handlers::verify::verify_email))
+        .route("/verify-email/resend", post(handlers::verify::resend_verification))
+        .route("/invitations/accept", post(handlers::invitations::accept_invitation))
+        .route("/token/refresh", post(handlers::refresh::refresh_token));
 
-    let protected_routes = Router::new()
+    // Also open to OAuth clients, limited by the permissions their scope delegates
+    let delegated_routes = Router::new()
         .route("/logout", post(handlers::logout::logout))
         .route("/protected", get(protected_root))
-        .layer(from_fn_with_state(pool.clone(), auth_middleware::<Body>));
+        .route("/users", get(handlers::users::list_users))
+        .route("/users/{id}", get(handlers::users::get_user))
+        .layer(from_fn_with_state(pool.clone(), auth_middleware));
+
+    let protected_routes = Router::new()
+        .route("/mfa/totp/enroll", post(handlers::mfa::enroll_totp))
+        .route("/mfa/totp/confirm", post(handlers::mfa::confirm_totp))
+        .route("/mfa/email/enable", post(handlers::mfa::enable_email_mfa))
+        .route("/mfa/recovery-codes", get(handlers::mfa::recovery_codes_status))
+        .route("/mfa/recovery-codes/regenerate", post(handlers::mfa::regenerate_recovery_codes))
+        .route("/webauthn/register/start", post(handlers::webauthn::start_registration))
+        .route("/webauthn/register/finish", post(handlers::webauthn::finish_registration))
+        .route("/users", post(handlers::users::create_user))
+        .route(
+            "/users/{id}",
+            put(handlers::users::update_user).delete(handlers::users::delete_user),
+        )
+        .route("/users/{id}/roles", put(handlers::roles::update_user_roles))
+        .route(
+            "/organizations",
+            get(handlers::organizations::list_organizations).post(handlers::organizations::create_org),
+        )
+        .route("/organizations/{id}/switch", post(handlers::organizations::switch_organization))
+        .route("/organizations/{id}/members", get(handlers::organizations::list_members))
+        .route(
+            "/organizations/{id}/members/{user_id}",
+            put(handlers::organizations::update_member).delete(handlers::organizations::remove_member),
+        )
+        .route(
+            "/organizations/{id}/invitations",
+            get(handlers::invitations::list_invitations).post(handlers::invitations::invite_member),
+        )
+        .route(
+            "/organizations/{id}/invitations/{invitation_id}",
+            delete(handlers::invitations::revoke),
+        )
+        .route(
+            "/roles",
+            get(handlers::roles::list_roles)
+                .route_layer(from_fn_with_state(RequireRole("admin"), require_role)),
+        )
+        .route(
+            "/admin/signing-keys",
+            get(handlers::signing_keys::list_keys)
+                .post(handlers::signing_keys::create)
+                .route_layer(from_fn_with_state(RequireRole("admin"), require_role)),
+        )
+        .route(
+            "/admin/signing-keys/{kid}/promote",
+            post(handlers::signing_keys::promote)
+                .route_layer(from_fn_with_state(RequireRole("admin"), require_role)),
+        )
+        .route(
+            "/admin/oauth-clients",
+            get(handlers::oauth_clients::list_clients)
+                .post(handlers::oauth_clients::create_client)
+                .route_layer(from_fn_with_state(RequireRole("admin"), require_role)),
+        )
+        .route(
+            "/admin/oauth-clients/{client_id}",
+            delete(handlers::oauth_clients::delete_client)
+                .route_layer(from_fn_with_state(RequireRole("admin"), require_role)),
+        )
+        .route_layer(from_fn(require_first_party))
+        .layer(from_fn_with_state(pool.clone(), auth_middleware));
+
+    let userinfo_routes = Router::new()
+        .route("/userinfo", get(handlers::userinfo::userinfo).post(handlers::userinfo::userinfo))
+        .layer(from_fn_with_state(pool.clone(), auth_middleware));
 
     Router::new()
+        .route("/.well-known/jwks.json", get(handlers::well_known::jwks))
+        .route("/.well-known/openid-configuration", get(handlers::well_known::openid_configuration))
+        .route(
+            "/oauth/authorize",
+            get(handlers::authorize::authorize).post(handlers::authorize::authorize_decision),
+        )
+        .route("/oauth/token", post(handlers::oauth::token))
+        .route("/oauth/device_authorization", post(handlers::oauth::device_authorization))
+        .route(
+            "/oauth/device",
+            get(handlers::device::verification).post(handlers::device::verification_decision),
+        )
+        .route("/oauth/introspect", post(handlers::oauth::introspect))
+        .route("/oauth/revoke", post(handlers::oauth::revoke))
         .nest("/api", public_routes)
         .nest("/api", login_routes)
+        .nest("/api", delegated_routes)
         .nest("/api", protected_routes)
+        .merge(userinfo_routes)
         .with_state(pool)
 }
\ No newline at end of file
@@ -1,5 +1,254 @@
 ﻿// @generated automatically by Diesel CLI.
 
+diesel::table! {
+    email_verification_tokens (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+        email -> Citext,
+    }
+}
+
+diesel::table! {
+    jwt_keys (id) {
+        id -> Int4,
+        #[max_length = 100]
+        kid -> Varchar,
+        #[max_length = 20]
+        purpose -> Varchar,
+        #[max_length = 10]
+        algorithm -> Varchar,
+        private_key -> Text,
+        #[max_length = 20]
+        status -> Varchar,
+        created_at -> Timestamp,
+        activated_at -> Nullable<Timestamp>,
+        retire_after -> Nullable<Timestamp>,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    magic_link_tokens (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        email -> Citext,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        #[max_length = 45]
+        requested_ip -> Nullable<Varchar>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    mfa_challenges (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        attempts -> Int2,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+        #[max_length = 64]
+        code_hash -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    mfa_recovery_codes (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        #[max_length = 64]
+        code_hash -> Varchar,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    oauth_authorization_codes (id) {
+        id -> Int4,
+        #[max_length = 64]
+        code_hash -> Varchar,
+        #[max_length = 64]
+        client_id -> Varchar,
+        user_id -> Uuid,
+        redirect_uri -> Text,
+        scope -> Text,
+        #[max_length = 128]
+        code_challenge -> Varchar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        family_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        nonce -> Nullable<Text>,
+        auth_time -> Timestamp,
+        redirect_uri_supplied -> Bool,
+    }
+}
+
+diesel::table! {
+    oauth_client_tokens (id) {
+        id -> Int4,
+        #[max_length = 64]
+        client_id -> Varchar,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        scope -> Text,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oauth_clients (client_id) {
+        #[max_length = 64]
+        client_id -> Varchar,
+        #[max_length = 64]
+        client_secret_hash -> Nullable<Varchar>,
+        #[max_length = 100]
+        name -> Varchar,
+        created_by -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        redirect_uris -> Array<Text>,
+        allowed_scopes -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    oauth_device_codes (id) {
+        id -> Int4,
+        #[max_length = 64]
+        device_code_hash -> Varchar,
+        #[max_length = 9]
+        user_code -> Varchar,
+        #[max_length = 64]
+        client_id -> Varchar,
+        scope -> Text,
+        user_id -> Nullable<Uuid>,
+        poll_interval -> Int4,
+        last_polled_at -> Nullable<Timestamp>,
+        approved_at -> Nullable<Timestamp>,
+        denied_at -> Nullable<Timestamp>,
+        auth_time -> Nullable<Timestamp>,
+        redeemed_at -> Nullable<Timestamp>,
+        expires_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    organization_invitations (id) {
+        id -> Int4,
+        organization_id -> Uuid,
+        email -> Citext,
+        #[max_length = 20]
+        role -> Varchar,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        invited_by -> Nullable<Uuid>,
+        expires_at -> Timestamp,
+        accepted_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    organization_members (organization_id, user_id) {
+        organization_id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 20]
+        role -> Varchar,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    organizations (id) {
+        id -> Uuid,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 50]
+        slug -> Varchar,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    password_reset_tokens (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        #[max_length = 45]
+        requested_ip -> Nullable<Varchar>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    permissions (id) {
+        id -> Int4,
+        #[max_length = 100]
+        name -> Varchar,
+        description -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    refresh_tokens (id) {
+        id -> Int4,
+        family_id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        created_at -> Timestamp,
+        rotated_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Int4,
+        permission_id -> Int4,
+    }
+}
+
+diesel::table! {
+    roles (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+        description -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    security_events (id) {
+        id -> Int4,
+        user_id -> Nullable<Uuid>,
+        #[max_length = 50]
+        event_type -> Varchar,
+        details -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     sessions (id) {
         id -> Int4,
@@ -8,6 +257,30 @@ diesel::table! {
         refresh_token -> Text,
         expires_at -> Timestamp,
         created_at -> Nullable<Timestamp>,
+        organization_id -> Nullable<Uuid>,
+        family_id -> Uuid,
+        #[max_length = 64]
+        client_id -> Nullable<Varchar>,
+        scope -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    totp_secrets (user_id) {
+        user_id -> Uuid,
+        #[max_length = 64]
+        secret -> Varchar,
+        confirmed_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<Int8>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    user_roles (user_id, role_id) {
+        user_id -> Uuid,
+        role_id -> Int4,
+        created_at -> Nullable<Timestamp>,
     }
 }
 
@@ -31,12 +304,91 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         deleted_at -> Nullable<Timestamptz>,
+        email_verified_at -> Nullable<Timestamptz>,
+        #[max_length = 20]
+        mfa_type -> Nullable<Varchar>,
+    }
+}
+
+diesel::joinable!(email_verification_tokens -> users (user_id));
+diesel::table! {
+    webauthn_challenges (id) {
+        id -> Int4,
+        user_id -> Nullable<Uuid>,
+        #[max_length = 64]
+        challenge -> Varchar,
+        #[max_length = 20]
+        ceremony -> Varchar,
+        expires_at -> Timestamp,
+        consumed_at -> Nullable<Timestamp>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    webauthn_credentials (id) {
+        id -> Int4,
+        user_id -> Uuid,
+        credential_id -> Text,
+        public_key -> Bytea,
+        sign_count -> Int8,
+        #[max_length = 100]
+        name -> Nullable<Varchar>,
+        created_at -> Nullable<Timestamp>,
+        last_used_at -> Nullable<Timestamp>,
     }
 }
 
+diesel::joinable!(magic_link_tokens -> users (user_id));
+diesel::joinable!(mfa_challenges -> users (user_id));
+diesel::joinable!(mfa_recovery_codes -> users (user_id));
+diesel::joinable!(oauth_authorization_codes -> oauth_clients (client_id));
+diesel::joinable!(oauth_authorization_codes -> users (user_id));
+diesel::joinable!(oauth_client_tokens -> oauth_clients (client_id));
+diesel::joinable!(oauth_clients -> users (created_by));
+diesel::joinable!(oauth_device_codes -> oauth_clients (client_id));
+diesel::joinable!(oauth_device_codes -> users (user_id));
+diesel::joinable!(organization_invitations -> organizations (organization_id));
+diesel::joinable!(organization_invitations -> users (invited_by));
+diesel::joinable!(organization_members -> organizations (organization_id));
+diesel::joinable!(organization_members -> users (user_id));
+diesel::joinable!(password_reset_tokens -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(role_permissions -> permissions (permission_id));
+diesel::joinable!(role_permissions -> roles (role_id));
+diesel::joinable!(security_events -> users (user_id));
+diesel::joinable!(sessions -> oauth_clients (client_id));
+diesel::joinable!(sessions -> organizations (organization_id));
 diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(totp_secrets -> users (user_id));
+diesel::joinable!(user_roles -> roles (role_id));
+diesel::joinable!(user_roles -> users (user_id));
+diesel::joinable!(webauthn_challenges -> users (user_id));
+diesel::joinable!(webauthn_credentials -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    email_verification_tokens,
+    jwt_keys,
+    magic_link_tokens,
+    mfa_challenges,
+    mfa_recovery_codes,
+    oauth_authorization_codes,
+    oauth_client_tokens,
+    oauth_clients,
+    oauth_device_codes,
+    organization_invitations,
+    organization_members,
+    organizations,
+    password_reset_tokens,
+    permissions,
+    refresh_tokens,
+    role_permissions,
+    roles,
+    security_events,
     sessions,
+    totp_secrets,
+    user_roles,
     users,
+    webauthn_challenges,
+    webauthn_credentials,
 );
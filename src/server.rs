@@ -20,5 +20,7 @@
         println!("Listening on {}", addr);
 
         // Start serving
-        axum::serve(listener, app).await.expect("Server error");
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .expect("Server error");
     }
\ No newline at end of file
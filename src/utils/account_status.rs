@@ -0,0 +1,14 @@
+// src/utils/account_status.rs
+
+use crate::config::allow_unverified_login;
+use crate::models::User;
+
+/// Whether the account may start or refresh a session. Suspended and inactive
+/// accounts never can; pending ones only while unverified logins are allowed.
+pub fn can_sign_in(user: &User) -> bool {
+    match user.status.as_str() {
+        "active" => true,
+        "pending_verification" => allow_unverified_login(),
+        _ => false,
+    }
+}
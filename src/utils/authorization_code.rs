@@ -0,0 +1,135 @@
+// src/utils/authorization_code.rs
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::models::{NewOAuthAuthorizationCode, OAuthAuthorizationCode};
+use crate::schema::oauth_authorization_codes::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+pub const AUTHORIZATION_CODE_LIFETIME_MINUTES: i64 = 5;
+
+/// What the user approved on the consent page.
+pub struct Approval {
+    pub client_id: String,
+    pub user_id: Uuid,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub code_challenge: String,
+    pub nonce: Option<String>,
+    /// When the user last authenticated, for the ID token's `auth_time`
+    pub auth_time: NaiveDateTime,
+    /// The request named `redirect_uri` rather than relying on the only registered one
+    pub redirect_uri_supplied: bool,
+}
+
+/// Issues a code for the approved request. Returns the raw code; only the
+/// hash is persisted.
+pub fn create_authorization_code(conn: &mut PgConnection, approval: Approval) -> QueryResult<String> {
+    let raw_code = generate_token();
+
+    diesel::insert_into(oauth_authorization_codes)
+        .values(&NewOAuthAuthorizationCode {
+            code_hash: hash_token(&raw_code),
+            client_id: approval.client_id,
+            user_id: approval.user_id,
+            redirect_uri: approval.redirect_uri,
+            scope: approval.scope,
+            code_challenge: approval.code_challenge,
+            expires_at: Utc::now().naive_utc() + Duration::minutes(AUTHORIZATION_CODE_LIFETIME_MINUTES),
+            nonce: approval.nonce,
+            auth_time: approval.auth_time,
+            redirect_uri_supplied: approval.redirect_uri_supplied,
+        })
+        .execute(conn)?;
+
+    Ok(raw_code)
+}
+
+/// Atomically marks an unused, unexpired code as used and returns it.
+pub fn consume_authorization_code(
+    conn: &mut PgConnection,
+    raw_code: &str,
+) -> QueryResult<Option<OAuthAuthorizationCode>> {
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        oauth_authorization_codes
+            .filter(code_hash.eq(hash_token(raw_code)))
+            .filter(used_at.is_null())
+            .filter(expires_at.gt(now)),
+    )
+    .set(used_at.eq(now))
+    .get_result::<OAuthAuthorizationCode>(conn)
+    .optional()
+}
+
+/// A code that has already been exchanged once.
+pub fn find_redeemed_code(
+    conn: &mut PgConnection,
+    raw_code: &str,
+) -> QueryResult<Option<OAuthAuthorizationCode>> {
+    oauth_authorization_codes
+        .filter(code_hash.eq(hash_token(raw_code)))
+        .filter(used_at.is_not_null())
+        .first::<OAuthAuthorizationCode>(conn)
+        .optional()
+}
+
+/// Remembers which refresh token family the code was exchanged for, so a
+/// replay of the code can revoke it.
+pub fn attach_family(conn: &mut PgConnection, code: &OAuthAuthorizationCode, family: Uuid) -> QueryResult<usize> {
+    diesel::update(oauth_authorization_codes.find(code.id))
+        .set(family_id.eq(family))
+        .execute(conn)
+}
+
+/// PKCE (RFC 7636) with the S256 method: the challenge is the unpadded
+/// base64url SHA-256 digest of the verifier.
+pub fn verify_code_challenge(verifier: &str, challenge: &str) -> bool {
+    let well_formed = (43..=128).contains(&verifier.len())
+        && verifier.chars().all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c));
+
+    well_formed && URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == challenge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from RFC 7636 appendix B
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn matching_verifier_passes() {
+        assert!(verify_code_challenge(VERIFIER, CHALLENGE));
+    }
+
+    #[test]
+    fn other_verifier_fails() {
+        let other = VERIFIER.replace('d', "e");
+        assert!(!verify_code_challenge(&other, CHALLENGE));
+        assert!(!verify_code_challenge(VERIFIER, VERIFIER));
+    }
+
+    #[test]
+    fn malformed_verifier_fails() {
+        // Too short, even when its challenge matches
+        let short = "a".repeat(42);
+        let short_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(short.as_bytes()));
+        assert!(!verify_code_challenge(&short, &short_challenge));
+
+        // Too long
+        let long = "a".repeat(129);
+        let long_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(long.as_bytes()));
+        assert!(!verify_code_challenge(&long, &long_challenge));
+
+        // Outside the unreserved character set
+        let spaced = format!("{} ", &VERIFIER[..43]);
+        let spaced_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(spaced.as_bytes()));
+        assert!(!verify_code_challenge(&spaced, &spaced_challenge));
+    }
+}
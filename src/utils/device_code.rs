@@ -0,0 +1,201 @@
+// src/utils/device_code.rs
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use rand::Rng;
+use uuid::Uuid;
+use crate::models::{NewOAuthDeviceCode, OAuthDeviceCode};
+use crate::schema::oauth_device_codes::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+pub const DEVICE_CODE_LIFETIME_MINUTES: i64 = 10;
+
+/// Seconds added to the polling interval each time a device polls too fast.
+const SLOW_DOWN_SECONDS: i32 = 5;
+
+/// Consonants only, so user codes are easy to type and never spell words
+/// (RFC 8628 section 6.1).
+const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+/// What a poll of the token endpoint found (RFC 8628 section 3.5).
+pub enum PollOutcome {
+    /// Approved; the code is now redeemed and cannot be polled again
+    Approved(Box<OAuthDeviceCode>),
+    Pending,
+    SlowDown,
+    Denied,
+    Expired,
+    /// Unknown, issued to another client, or already redeemed
+    Invalid,
+}
+
+/// Eight characters in two groups, e.g. `BCDF-GHJK`.
+fn generate_user_code() -> String {
+    let mut rng = rand::thread_rng();
+    let chars: String = (0..8)
+        .map(|_| USER_CODE_CHARSET[rng.gen_range(0..USER_CODE_CHARSET.len())] as char)
+        .collect();
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+/// Accepts what the user typed regardless of case, spaces or dashes.
+pub fn normalize_user_code(input: &str) -> Option<String> {
+    let chars: String = input
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    (chars.len() == 8).then(|| format!("{}-{}", &chars[..4], &chars[4..]))
+}
+
+/// Starts a device authorization request. Returns the raw device code with the
+/// stored request; only the device code's hash is persisted. Expired requests
+/// are pruned first so their user codes can be handed out again.
+pub fn create_device_code(
+    conn: &mut PgConnection,
+    client: &str,
+    granted_scope: &str,
+) -> QueryResult<(String, OAuthDeviceCode)> {
+    let now = Utc::now().naive_utc();
+    diesel::delete(oauth_device_codes.filter(expires_at.lt(now))).execute(conn)?;
+
+    let raw_code = generate_token();
+    let stored = diesel::insert_into(oauth_device_codes)
+        .values(&NewOAuthDeviceCode {
+            device_code_hash: hash_token(&raw_code),
+            user_code: generate_user_code(),
+            client_id: client.to_string(),
+            scope: granted_scope.to_string(),
+            expires_at: now + Duration::minutes(DEVICE_CODE_LIFETIME_MINUTES),
+        })
+        .get_result::<OAuthDeviceCode>(conn)?;
+
+    Ok((raw_code, stored))
+}
+
+/// An unexpired request that is still waiting for the user's decision.
+pub fn find_pending_request(conn: &mut PgConnection, code: &str) -> QueryResult<Option<OAuthDeviceCode>> {
+    oauth_device_codes
+        .filter(user_code.eq(code))
+        .filter(approved_at.is_null())
+        .filter(denied_at.is_null())
+        .filter(expires_at.gt(Utc::now().naive_utc()))
+        .first::<OAuthDeviceCode>(conn)
+        .optional()
+}
+
+/// Records the user's decision. `approver` is `None` when the request was
+/// denied. Returns false if the request was decided or expired meanwhile.
+pub fn decide_request(
+    conn: &mut PgConnection,
+    request: &OAuthDeviceCode,
+    approver: Option<(Uuid, NaiveDateTime)>,
+) -> QueryResult<bool> {
+    let now = Utc::now().naive_utc();
+    let pending = oauth_device_codes
+        .find(request.id)
+        .filter(approved_at.is_null())
+        .filter(denied_at.is_null())
+        .filter(expires_at.gt(now));
+
+    let updated = match approver {
+        Some((approver_id, authenticated_at)) => diesel::update(pending)
+            .set((
+                user_id.eq(approver_id),
+                approved_at.eq(now),
+                auth_time.eq(authenticated_at),
+            ))
+            .execute(conn)?,
+        None => diesel::update(pending).set(denied_at.eq(now)).execute(conn)?,
+    };
+
+    Ok(updated > 0)
+}
+
+/// Handles one poll from the device. Polling faster than the current interval
+/// widens it; an approved request is redeemed atomically, so it yields tokens
+/// only once.
+pub fn poll_device_code(conn: &mut PgConnection, client: &str, raw_code: &str) -> QueryResult<PollOutcome> {
+    let now = Utc::now().naive_utc();
+
+    let request = match oauth_device_codes
+        .filter(device_code_hash.eq(hash_token(raw_code)))
+        .filter(client_id.eq(client))
+        .first::<OAuthDeviceCode>(conn)
+        .optional()?
+    {
+        Some(request) if request.redeemed_at.is_none() => request,
+        _ => return Ok(PollOutcome::Invalid),
+    };
+
+    if request.expires_at <= now {
+        return Ok(PollOutcome::Expired);
+    }
+    if request.denied_at.is_some() {
+        return Ok(PollOutcome::Denied);
+    }
+
+    let too_fast = request
+        .last_polled_at
+        .is_some_and(|last| now < last + Duration::seconds(request.poll_interval as i64));
+    if too_fast {
+        diesel::update(oauth_device_codes.find(request.id))
+            .set((poll_interval.eq(poll_interval + SLOW_DOWN_SECONDS), last_polled_at.eq(now)))
+            .execute(conn)?;
+        return Ok(PollOutcome::SlowDown);
+    }
+
+    if request.approved_at.is_none() {
+        diesel::update(oauth_device_codes.find(request.id))
+            .set(last_polled_at.eq(now))
+            .execute(conn)?;
+        return Ok(PollOutcome::Pending);
+    }
+
+    let redeemed = diesel::update(
+        oauth_device_codes
+            .find(request.id)
+            .filter(redeemed_at.is_null()),
+    )
+    .set((redeemed_at.eq(now), last_polled_at.eq(now)))
+    .get_result::<OAuthDeviceCode>(conn)
+    .optional()?;
+
+    Ok(redeemed.map_or(PollOutcome::Invalid, |request| PollOutcome::Approved(Box::new(request))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_case() {
+        assert_eq!(normalize_user_code("bcdf-ghjk").as_deref(), Some("BCDF-GHJK"));
+        assert_eq!(normalize_user_code("BcDf-gHjK").as_deref(), Some("BCDF-GHJK"));
+    }
+
+    #[test]
+    fn strips_dashes_and_whitespace() {
+        assert_eq!(normalize_user_code("BCDFGHJK").as_deref(), Some("BCDF-GHJK"));
+        assert_eq!(normalize_user_code(" bcdf ghjk\n").as_deref(), Some("BCDF-GHJK"));
+        assert_eq!(normalize_user_code("B-C-D-F-G-H-J-K").as_deref(), Some("BCDF-GHJK"));
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        for input in ["", "----", "BCDF-GHJ", "BCDF-GHJKL", "BCDFGHJKBCDFGHJK"] {
+            assert_eq!(normalize_user_code(input), None, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn generated_codes_normalize_to_themselves() {
+        for _ in 0..100 {
+            let code = generate_user_code();
+
+            assert!(code.bytes().filter(|b| *b != b'-').all(|b| USER_CODE_CHARSET.contains(&b)));
+            assert_eq!(normalize_user_code(&code.to_ascii_lowercase()), Some(code));
+        }
+    }
+}
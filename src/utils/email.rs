@@ -7,37 +7,30 @@ use crate::utils::error::AppError;
 use dotenvy::dotenv;
 use chrono::NaiveDateTime;
 
-pub async fn send_password_reset_email(
-    to_email: &str,
-    reset_token: &str,
-    token_expiration: NaiveDateTime
-) -> Result<(), AppError> {
+fn frontend_url() -> Result<String, AppError> {
+    env::var("FRONTEND_URL")
+        .map_err(|_| AppError::InternalServerError("Frontend URL not configured".to_string()))
+}
+
+async fn send_email(to_email: &str, subject: &str, body: String) -> Result<(), AppError> {
     dotenv().ok();  // Load .env file
-    
+
     let smtp_username = env::var("SMTP_USERNAME")
         .map_err(|_| AppError::InternalServerError("SMTP username not configured".to_string()))?;
     let smtp_password = env::var("SMTP_PASSWORD")
         .map_err(|_| AppError::InternalServerError("SMTP password not configured".to_string()))?;
     let smtp_host = env::var("SMTP_HOST")
         .map_err(|_| AppError::InternalServerError("SMTP host not configured".to_string()))?;
-    let frontend_url = env::var("FRONTEND_URL")
-        .map_err(|_| AppError::InternalServerError("Frontend URL not configured".to_string()))?;
 
     let email = Message::builder()
-        .from(smtp_username.parse().unwrap())
-        .to(to_email.parse().unwrap())
-        .subject("Password Reset Request")
-        .body(format!(
-            "Click the following link to reset your password:\n{}/reset-password/{} \n\nThis link will expire on {}.",
-            frontend_url,
-            reset_token,
-            token_expiration.format("%Y-%m-%d %H:%M:%S")
-        ))
-        .unwrap();
+        .from(smtp_username.parse()?)
+        .to(to_email.parse()?)
+        .subject(subject)
+        .body(body)?;
 
     let creds = Credentials::new(smtp_username, smtp_password);
 
-    let mailer = SmtpTransport::relay(&smtp_host).unwrap()
+    let mailer = SmtpTransport::relay(&smtp_host)?
         .credentials(creds)
         .build();
 
@@ -47,4 +40,74 @@ pub async fn send_password_reset_email(
         Err(e) => return Err(AppError::InternalServerError(format!("Could not send email: {}", e))),
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Sends a single-use link of the form `{FRONTEND_URL}/{path}/{token}`.
+async fn send_token_link_email(
+    to_email: &str,
+    subject: &str,
+    action: &str,
+    path: &str,
+    token: &str,
+    token_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    let body = format!(
+        "Click the following link to {}:\n{}/{}/{} \n\nThis link will expire on {}.",
+        action,
+        frontend_url()?,
+        path,
+        token,
+        token_expiration.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    send_email(to_email, subject, body).await
+}
+
+pub async fn send_password_reset_email(
+    to_email: &str,
+    reset_token: &str,
+    token_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    send_token_link_email(to_email, "Password Reset Request", "reset your password", "reset-password", reset_token, token_expiration).await
+}
+
+pub async fn send_verification_email(
+    to_email: &str,
+    verification_token: &str,
+    token_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    send_token_link_email(to_email, "Verify Your Email Address", "verify your email address", "verify-email", verification_token, token_expiration).await
+}
+
+pub async fn send_magic_link_email(
+    to_email: &str,
+    login_token: &str,
+    token_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    send_token_link_email(to_email, "Your Login Link", "log in", "magic-link", login_token, token_expiration).await
+}
+
+pub async fn send_invitation_email(
+    to_email: &str,
+    organization_name: &str,
+    invitation_token: &str,
+    token_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    let subject = format!("You're invited to join {}", organization_name);
+    let action = format!("join {}", organization_name);
+    send_token_link_email(to_email, &subject, &action, "accept-invitation", invitation_token, token_expiration).await
+}
+
+pub async fn send_login_code_email(
+    to_email: &str,
+    code: &str,
+    code_expiration: NaiveDateTime
+) -> Result<(), AppError> {
+    let body = format!(
+        "Your login verification code is: {}\n\nThis code will expire on {}. If you did not try to log in, change your password.",
+        code,
+        code_expiration.format("%Y-%m-%d %H:%M:%S")
+    );
+
+    send_email(to_email, "Your Login Code", body).await
+}
@@ -13,6 +13,8 @@ pub enum AppError {
     EmailError(String),
     ConfigError(String),
     ValidationError(String),
+    Forbidden(String),
+    NotFound(String),
     InternalServerError(String),
 }
 
@@ -23,6 +25,8 @@ impl IntoResponse for AppError {
             AppError::EmailError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
             AppError::ConfigError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
             AppError::ValidationError(e) => (StatusCode::BAD_REQUEST, e),
+            AppError::Forbidden(e) => (StatusCode::FORBIDDEN, e),
+            AppError::NotFound(e) => (StatusCode::NOT_FOUND, e),
             AppError::InternalServerError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
         };
 
@@ -35,6 +39,17 @@ impl IntoResponse for AppError {
 }
 
 // Implement `From` traits for easy error conversion
+/// Usernames and emails are unique, so a clash when storing an account is the
+/// caller's mistake.
+pub fn conflict_as_validation(err: diesel::result::Error) -> AppError {
+    match err {
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _) => {
+            AppError::ValidationError("Username or email is already taken".to_string())
+        },
+        err => AppError::DbError(err),
+    }
+}
+
 impl From<diesel::result::Error> for AppError {
     fn from(err: diesel::result::Error) -> Self {
         AppError::DbError(err)
@@ -71,6 +86,8 @@ impl std::fmt::Display for AppError {
             AppError::EmailError(e) => write!(f, "Email error: {}", e),
             AppError::ConfigError(e) => write!(f, "Configuration error: {}", e),
             AppError::ValidationError(e) => write!(f, "Validation error: {}", e),
+            AppError::Forbidden(e) => write!(f, "Forbidden: {}", e),
+            AppError::NotFound(e) => write!(f, "Not found: {}", e),
             AppError::InternalServerError(e) => write!(f, "Internal server error: {}", e),
         }
     }
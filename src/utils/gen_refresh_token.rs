@@ -1,47 +1,131 @@
 use std::env;
-use std::error::Error;
 use diesel::RunQueryDsl;
 use diesel::prelude::*;
 use chrono::{Utc, Duration};
-use crate::models::Session;
+use crate::models::{RefreshToken, Session, User};
+use crate::schema::users;
 use crate::schema::sessions::dsl::*;
 use crate::schema::sessions::{expires_at, refresh_token, token};
+use crate::utils::account_status::can_sign_in;
 use crate::utils::jwt::generate_jwt;
 use crate::utils::jwt_validator::validate_refresh_token;
+use crate::utils::permissions::resolve_grants;
+use crate::utils::secure_token::hash_token;
+use crate::utils::security_event::{record_security_event, REFRESH_TOKEN_REUSE};
+use crate::utils::token_family::{find_refresh_token, is_spent, mark_rotated, record_refresh_token, revoke_family};
 
-pub async fn refresh_tokens(refresh_token_str: &str, conn: &mut PgConnection) -> Result<(String, String), String> {
+/// A refresh token that was already exchanged means someone else holds a
+/// copy of it, so the whole family is revoked (OAuth 2.0 Security BCP).
+fn reuse_detected(conn: &mut PgConnection, stored: &RefreshToken) -> String {
+    let details = match revoke_family(conn, stored.family_id) {
+        Ok(revoked) => format!(
+            "Refresh token {} of family {} was presented again; revoked {} session(s)",
+            stored.id, stored.family_id, revoked
+        ),
+        Err(e) => {
+            log::error!("Failed to revoke refresh token family {}: {}", stored.family_id, e);
+            format!(
+                "Refresh token {} of family {} was presented again; revoking the family failed",
+                stored.id, stored.family_id
+            )
+        },
+    };
+    if let Err(e) = record_security_event(conn, Some(stored.user_id), REFRESH_TOKEN_REUSE, details) {
+        log::error!("Failed to record security event: {}", e);
+    }
+    "Refresh token reuse detected".to_string()
+}
+
+/// Revokes the token's family if the refresh token has already been rotated.
+/// Returns `true` if it had.
+pub fn detect_reuse(conn: &mut PgConnection, refresh_token_str: &str) -> bool {
+    match find_refresh_token(conn, refresh_token_str) {
+        Ok(Some(stored)) if is_spent(&stored) => {
+            reuse_detected(conn, &stored);
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Rotates a refresh token. `client` is the authenticated OAuth client asking,
+/// or `None` for first-party refreshes; a token is only refreshed for the
+/// client it was issued to.
+pub async fn refresh_tokens(
+    refresh_token_str: &str,
+    conn: &mut PgConnection,
+    client: Option<&str>,
+) -> Result<(String, String), String> {
     // 1. Validate refresh token
     let token_data = validate_refresh_token(refresh_token_str).await
         .map_err(|e| format!("Invalid refresh token: {}", e))?;
 
-    // 2. Check if refresh token is in database and not expired
+    // 2. Check that the token was issued by us and has not been used before
+    let stored = find_refresh_token(conn, refresh_token_str)
+        .map_err(|e| format!("Failed to look up refresh token: {}", e))?
+        .ok_or("Refresh token not found in database")?;
+    if is_spent(&stored) {
+        return Err(reuse_detected(conn, &stored));
+    }
+
     let session = sessions
-        .filter(refresh_token.eq(refresh_token_str))
+        .filter(family_id.eq(stored.family_id))
+        .filter(refresh_token.eq(hash_token(refresh_token_str)))
         .first::<Session>(conn)
         .map_err(|_| "Refresh token not found in database")?;
+    // Tokens of OAuth clients are only refreshed at /oauth/token, where
+    // confidential clients have to authenticate
+    if session.client_id.as_deref() != client {
+        return Err("Refresh token was issued to another client".to_string());
+    }
 
     if token_data.claims.exp < Utc::now().timestamp() as usize {
         return Err("Refresh token has expired".to_string());
     }
 
     // 3. Generate new tokens
-    let jwt_secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_key".to_string());
-    let jwt_secret_x = env::var("JWT_SECRET_X").unwrap_or_else(|_| "default_refresh_secret_key".to_string());
+    // Re-resolve roles and permissions so changes apply from the next refresh
+    let user = users::table
+        .find(session.user_id)
+        .filter(users::deleted_at.is_null())
+        .first::<User>(conn)
+        .map_err(|_| "User not found")?;
+    if !can_sign_in(&user) {
+        return Err("Account is disabled".to_string());
+    }
+    let mut grants = resolve_grants(conn, &user, session.organization_id)
+        .map_err(|e| format!("Failed to resolve permissions: {}", e))?;
+    if let Some(client) = &session.client_id {
+        grants = grants.for_client(client, session.scope.as_deref().unwrap_or_default());
+    }
 
-    let new_access_token = generate_jwt(session.user_id.to_string(), &jwt_secret, false)
+    let new_access_token = generate_jwt(session.user_id, &grants, false)
         .map_err(|e| format!("Failed to generate access token: {}", e))?;
-    let new_refresh_token = generate_jwt(session.user_id.to_string(), &jwt_secret_x, true)
+    let new_refresh_token = generate_jwt(session.user_id, &grants, true)
         .map_err(|e| format!("Failed to generate refresh token: {}", e))?;
 
-    diesel::update(sessions)
-        .filter(id.eq(session.id))
-        .set((
-            token.eq(&new_access_token),
-            refresh_token.eq(&new_refresh_token),
-            expires_at.eq(Utc::now().naive_utc() + Duration::minutes(env::var("ACCESS_TOKEN_EXP_DURATION").unwrap().parse::<i64>().unwrap()))
-        ))
-        .execute(conn)
-        .map_err(|e| format!("Failed to update session: {}", e))?;
+    // 4. Rotate within the family; losing the race to another request is reuse too
+    let rotated = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        if !mark_rotated(conn, &stored)? {
+            return Ok(false);
+        }
+        record_refresh_token(conn, stored.family_id, session.user_id, &new_refresh_token)?;
+
+        diesel::update(sessions)
+            .filter(id.eq(session.id))
+            .set((
+                token.eq(hash_token(&new_access_token)),
+                refresh_token.eq(hash_token(&new_refresh_token)),
+                organization_id.eq(grants.organization_id),
+                expires_at.eq(Utc::now().naive_utc() + Duration::minutes(env::var("ACCESS_TOKEN_EXP_DURATION").unwrap().parse::<i64>().unwrap()))
+            ))
+            .execute(conn)?;
+        Ok(true)
+    }).map_err(|e| format!("Failed to update session: {}", e))?;
+
+    if !rotated {
+        return Err(reuse_detected(conn, &stored));
+    }
 
     Ok((new_access_token, new_refresh_token))
-}
\ No newline at end of file
+}
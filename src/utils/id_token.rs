@@ -0,0 +1,102 @@
+// src/utils/id_token.rs
+
+use chrono::{NaiveDateTime, Utc};
+use std::sync::Arc;
+use jsonwebtoken::{encode, Algorithm, Header};
+use serde::Serialize;
+use crate::config::get_jwt_issuer;
+use crate::models::User;
+use crate::utils::jwt::token_lifetime_minutes;
+use crate::utils::signing_key::{signing_key, SigningKey};
+
+/// Standard OpenID Connect claims about the user.
+#[derive(Serialize, Default)]
+pub struct UserClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: String,
+    iat: usize,
+    exp: usize,
+    auth_time: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(flatten)]
+    user: UserClaims,
+}
+
+/// Whether `scope` includes `wanted`. Tokens from our own login carry no
+/// scope and are not restricted.
+pub fn has_scope(scope: Option<&str>, wanted: &str) -> bool {
+    scope.is_none_or(|scope| scope.split_whitespace().any(|s| s == wanted))
+}
+
+/// Releases `email` claims for the `email` scope and name claims for `profile`.
+pub fn user_claims(user: &User, scope: Option<&str>) -> UserClaims {
+    let mut claims = UserClaims::default();
+    if has_scope(scope, "email") {
+        claims.email = Some(user.email.clone());
+        claims.email_verified = Some(user.email_verified_at.is_some());
+    }
+    if has_scope(scope, "profile") {
+        claims.preferred_username = Some(user.username.clone());
+        claims.name = user.full_name.clone();
+    }
+    claims
+}
+
+/// The access token key, if clients can verify it against the published
+/// JWKS. An HS256 key is the server's own secret and is never published.
+fn id_token_key() -> Result<Arc<SigningKey>, String> {
+    let key = signing_key(false)?;
+    if key.algorithm == Algorithm::HS256 {
+        return Err("OpenID Connect needs an asymmetric JWT_ALGORITHM".to_string());
+    }
+    Ok(key)
+}
+
+/// Whether ID tokens can be issued, i.e. the `openid` scope is available.
+pub fn oidc_enabled() -> bool {
+    id_token_key().is_ok()
+}
+
+/// Signs an ID token for `client_id` with the access token key, so clients
+/// verify it against the published JWKS.
+pub fn generate_id_token(
+    user: &User,
+    client_id: &str,
+    scope: &str,
+    nonce: Option<String>,
+    auth_time: NaiveDateTime,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let now = Utc::now().timestamp();
+
+    let claims = IdTokenClaims {
+        iss: get_jwt_issuer(),
+        sub: user.id.to_string(),
+        aud: client_id.to_string(),
+        iat: now as usize,
+        exp: (now + token_lifetime_minutes(false) * 60) as usize,
+        auth_time: auth_time.and_utc().timestamp() as usize,
+        nonce,
+        user: user_claims(user, Some(scope)),
+    };
+
+    let key = id_token_key()?;
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+
+    encode(&header, &claims, &key.encoding)
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+}
@@ -0,0 +1,106 @@
+// src/utils/invitation.rs
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{NewOrganizationInvitation, OrganizationInvitation};
+use crate::schema::organization_invitations::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+pub const INVITATION_LIFETIME_DAYS: i64 = 7;
+
+/// Creates an invitation and revokes any earlier pending one for the same
+/// address. Returns the stored invitation with the raw token; only the hash
+/// is persisted.
+pub fn create_invitation(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    invitee_email: &str,
+    invitee_role: &str,
+    inviter: Uuid,
+) -> QueryResult<(OrganizationInvitation, String)> {
+    let raw_token = generate_token();
+    let now = Utc::now().naive_utc();
+
+    conn.transaction(|conn| {
+        diesel::update(
+            organization_invitations
+                .filter(organization_id.eq(org_id))
+                .filter(email.eq(invitee_email))
+                .filter(accepted_at.is_null())
+                .filter(revoked_at.is_null()),
+        )
+        .set(revoked_at.eq(now))
+        .execute(conn)?;
+
+        let invitation = diesel::insert_into(organization_invitations)
+            .values(&NewOrganizationInvitation {
+                organization_id: org_id,
+                email: invitee_email.to_string(),
+                role: invitee_role.to_string(),
+                token_hash: hash_token(&raw_token),
+                invited_by: Some(inviter),
+                expires_at: now + Duration::days(INVITATION_LIFETIME_DAYS),
+            })
+            .get_result::<OrganizationInvitation>(conn)?;
+
+        Ok((invitation, raw_token))
+    })
+}
+
+/// Invitations that can still be accepted.
+fn pending(now: NaiveDateTime) -> crate::schema::organization_invitations::BoxedQuery<'static, diesel::pg::Pg> {
+    organization_invitations
+        .filter(accepted_at.is_null())
+        .filter(revoked_at.is_null())
+        .filter(expires_at.gt(now))
+        .into_boxed()
+}
+
+pub fn pending_invitations(conn: &mut PgConnection, org_id: Uuid) -> QueryResult<Vec<OrganizationInvitation>> {
+    pending(Utc::now().naive_utc())
+        .filter(organization_id.eq(org_id))
+        .order(created_at.desc())
+        .load::<OrganizationInvitation>(conn)
+}
+
+/// Looks up a pending invitation without using it up.
+pub fn find_invitation(conn: &mut PgConnection, raw_token: &str) -> QueryResult<Option<OrganizationInvitation>> {
+    pending(Utc::now().naive_utc())
+        .filter(token_hash.eq(hash_token(raw_token)))
+        .first::<OrganizationInvitation>(conn)
+        .optional()
+}
+
+/// Atomically marks a pending invitation as accepted and returns it.
+pub fn accept_invitation(conn: &mut PgConnection, raw_token: &str) -> QueryResult<Option<OrganizationInvitation>> {
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        organization_invitations
+            .filter(token_hash.eq(hash_token(raw_token)))
+            .filter(accepted_at.is_null())
+            .filter(revoked_at.is_null())
+            .filter(expires_at.gt(now)),
+    )
+    .set(accepted_at.eq(now))
+    .get_result::<OrganizationInvitation>(conn)
+    .optional()
+}
+
+/// Revokes a pending invitation. Returns `false` if there was none to revoke.
+pub fn revoke_invitation(conn: &mut PgConnection, org_id: Uuid, invitation_id: i32) -> QueryResult<bool> {
+    let now = Utc::now().naive_utc();
+
+    let revoked = diesel::update(
+        organization_invitations
+            .filter(id.eq(invitation_id))
+            .filter(organization_id.eq(org_id))
+            .filter(accepted_at.is_null())
+            .filter(revoked_at.is_null()),
+    )
+    .set(revoked_at.eq(now))
+    .execute(conn)?;
+
+    Ok(revoked == 1)
+}
@@ -1,21 +1,57 @@
 // src/utils/jwt.rs
 
 use std::env;
-use jsonwebtoken::{encode, Header, EncodingKey};
+use jsonwebtoken::{encode, Algorithm, Header, Validation};
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
 use diesel::prelude::*;
+use uuid::Uuid;
+use crate::config::{get_jwt_audience, get_jwt_issuer};
 use crate::schema::users::dsl::{users, username, login_attempts, last_login_at};
+use crate::utils::permissions::Grants;
+use crate::utils::signing_key::signing_key;
 
+/// Claims carried by both access and refresh tokens.
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
-    sub: String,
-    exp: usize,
+    /// `users.id` of the account the token was issued to, or the client id
+    /// for machine tokens
+    pub(crate) sub: String,
+    pub(crate) iss: String,
+    pub(crate) aud: String,
+    pub(crate) iat: usize,
+    pub(crate) nbf: usize,
+    pub(crate) exp: usize,
+    pub(crate) jti: String,
     pub refresh: bool,
+    pub(crate) role: String,
+    pub(crate) roles: Vec<String>,
+    pub(crate) permissions: Vec<String>,
+    pub(crate) org: Option<Uuid>,
+    pub(crate) org_role: Option<String>,
+    /// Set on tokens issued to an OAuth client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) scope: Option<String>,
+    /// Issued to a client acting on its own behalf (`client_credentials`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) machine: bool,
 }
 
-pub fn generate_jwt(user_name: String, secret: &str, refresh: bool) -> Result<String, Box<dyn std::error::Error>> {
-    let duration = if refresh {
+impl Claims {
+    /// The account the token was issued to.
+    pub fn user_id(&self) -> Option<Uuid> {
+        if self.machine {
+            return None;
+        }
+        Uuid::parse_str(&self.sub).ok()
+    }
+}
+
+/// Minutes a token stays valid, from `ACCESS_TOKEN_EXP_DURATION` / `REFRESH_TOKEN_EXP_DURATION`.
+pub fn token_lifetime_minutes(refresh: bool) -> i64 {
+    if refresh {
         env::var("REFRESH_TOKEN_EXP_DURATION")
             .unwrap_or_else(|_| "60".to_string())
             .parse::<i64>()
@@ -25,24 +61,106 @@ pub fn generate_jwt(user_name: String, secret: &str, refresh: bool) -> Result<St
             .unwrap_or_else(|_| "15".to_string())
             .parse::<i64>()
             .unwrap_or(15)
-    };
+    }
+}
+
+/// Minutes a `client_credentials` token stays valid, from `CLIENT_TOKEN_EXP_DURATION`.
+pub fn client_token_lifetime_minutes() -> i64 {
+    env::var("CLIENT_TOKEN_EXP_DURATION")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse::<i64>()
+        .unwrap_or(5)
+}
+
+/// Access tokens are meant for `JWT_AUDIENCE`; refresh tokens are only ever
+/// presented back to the issuer.
+fn audience(refresh: bool) -> String {
+    if refresh {
+        get_jwt_issuer()
+    } else {
+        get_jwt_audience()
+    }
+}
+
+/// What every token has to satisfy besides its signature.
+pub fn validation(algorithm: Algorithm, refresh: bool) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(&[get_jwt_issuer()]);
+    validation.set_audience(&[audience(refresh)]);
+    validation.set_required_spec_claims(&["exp", "nbf", "iss", "aud", "sub"]);
+    validation.validate_nbf = true;
+    validation
+}
+
+pub fn generate_jwt(user_id: Uuid, grants: &Grants, refresh: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let duration = token_lifetime_minutes(refresh);
 
-    let expiration = Utc::now()
+    let now = Utc::now();
+    let expiration = now
         .checked_add_signed(chrono::Duration::minutes(duration))
         .ok_or("Invalid timestamp calculation")?
         .timestamp();
 
     let claims = Claims {
-        sub: user_name.to_owned(),
+        sub: user_id.to_string(),
+        iss: get_jwt_issuer(),
+        aud: audience(refresh),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
         exp: expiration as usize,
+        jti: Uuid::new_v4().to_string(),
         refresh,
+        role: grants.role.clone(),
+        roles: grants.roles.clone(),
+        permissions: grants.permissions.clone(),
+        org: grants.organization_id,
+        org_role: grants.org_role.clone(),
+        client_id: grants.client_id.clone(),
+        scope: grants.scope.clone(),
+        machine: false,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref())
-    ).map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+    sign(&claims, refresh)
+}
+
+/// Access token for a client acting on its own behalf. It carries no user,
+/// roles or organization; `permissions` are the ones named in `scope`.
+pub fn generate_client_jwt(client_id: &str, scope: &str, permissions: Vec<String>) -> Result<String, Box<dyn std::error::Error>> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(chrono::Duration::minutes(client_token_lifetime_minutes()))
+        .ok_or("Invalid timestamp calculation")?
+        .timestamp();
+
+    let claims = Claims {
+        sub: client_id.to_string(),
+        iss: get_jwt_issuer(),
+        aud: audience(false),
+        iat: now.timestamp() as usize,
+        nbf: now.timestamp() as usize,
+        exp: expiration as usize,
+        jti: Uuid::new_v4().to_string(),
+        refresh: false,
+        role: String::new(),
+        roles: Vec::new(),
+        permissions,
+        org: None,
+        org_role: None,
+        client_id: Some(client_id.to_string()),
+        scope: Some(scope.to_string()),
+        machine: true,
+    };
+
+    sign(&claims, false)
+}
+
+fn sign(claims: &Claims, refresh: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let key = signing_key(refresh)?;
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+
+    encode(&header, claims, &key.encoding)
+        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
 }
 
 
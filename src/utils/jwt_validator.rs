@@ -1,43 +1,36 @@
 // src/utils/jwt_validator.rs
 
-use std::env;
-use axum::extract::State;
-use axum_extra::headers::Authorization;
-use axum_extra::headers::authorization::Bearer;
-use axum_extra::TypedHeader;
-use jsonwebtoken::{decode, DecodingKey, Validation, TokenData};
-use serde::{Serialize, Deserialize};
+use jsonwebtoken::{decode, decode_header, TokenData};
 use chrono::Utc;
-use crate::db::PgPool;
+use uuid::Uuid;
 use crate::schema::sessions::dsl::sessions;
-use crate::schema::sessions::token;
+use crate::schema::sessions::{client_id as session_client_id, family_id, token};
 use diesel::prelude::*;
 use jsonwebtoken::errors::ErrorKind;
-use crate::models::Session;
-
-#[derive(Serialize, Deserialize)]
-pub struct Claims {
-    pub(crate) sub: String,
-    pub(crate) exp: usize,
-    pub refresh: bool,
-}
+use crate::utils::jwt::{validation, Claims};
+use crate::utils::oauth_client::revoke_client_token;
+use crate::utils::secure_token::hash_token;
+use crate::utils::signing_key::verification_key;
+use crate::utils::token_family::{find_refresh_token, revoke_family};
 
 fn is_token_expired(exp: usize) -> bool {
     exp < Utc::now().timestamp() as usize
 }
 
-pub async fn validate_jwt(token_y: &str) -> Result<TokenData<Claims>, String> {
-    // First validate JWT signature and expiration
-    let validation = Validation::default();
-    let jwt_secret = env::var("JWT_SECRET").map_err(|_| "JWT_SECRET not set")?;
-    
+/// Checks an access token's signature and expiry without consulting the database.
+pub fn decode_access_token(token_y: &str) -> Result<TokenData<Claims>, String> {
+    let header = decode_header(token_y).map_err(|_| "Invalid token format")?;
+    let key = verification_key(header.kid.as_deref(), false)?;
+
     let token_data = decode::<Claims>(
         token_y,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &validation
+        &key.decoding,
+        &validation(key.algorithm, false)
     ).map_err(|err| match *err.kind() {
         ErrorKind::ExpiredSignature => "Token has expired",
         ErrorKind::InvalidSignature => "Invalid token signature",
+        ErrorKind::ImmatureSignature => "Token is not valid yet",
+        ErrorKind::InvalidIssuer | ErrorKind::InvalidAudience => "Token was not issued for this service",
         _ => "Invalid token format",
     })?;
 
@@ -45,36 +38,68 @@ pub async fn validate_jwt(token_y: &str) -> Result<TokenData<Claims>, String> {
         return Err("Token has expired".to_string());
     }
 
-    // Then check if token exists in database
-    let database_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not set")?;
-    let mut conn = PgConnection::establish(&database_url)
-        .map_err(|_| "Failed to connect to database")?;
-
-    sessions
-        .filter(token.eq(token_y))
-        .first::<Session>(&mut conn)
-        .map_err(|_| "Token not found in database")?;
-
     Ok(token_data)
 }
 
-pub async fn invalidate_token(
-    State(pool): State<PgPool>,
-    TypedHeader(Authorization(bearer)): TypedHeader<Authorization<Bearer>>,
-) -> Result<(), String> {
-    let token_str = bearer.token();
-    let mut conn = pool.get().map_err(|_| "Failed to get DB connection from pool".to_string())?;
-    diesel::delete(sessions.filter(token.eq(token_str)))
-        .execute(&mut conn)
-        .map_err(|_| "Failed to invalidate token".to_string())?;
-    Ok(())
+/// Revokes the session an access or refresh token belongs to, together with
+/// its refresh token family, or a `client_credentials` token. With `client`,
+/// only tokens issued to that OAuth client count (RFC 7009 section 2.1).
+/// Returns whether the token was known. Only the digest is compared, so the
+/// token's signature does not matter here.
+pub fn invalidate_token(conn: &mut PgConnection, raw_token: &str, hint: Option<&str>, client: Option<&str>) -> QueryResult<bool> {
+    if revoke_client_token(conn, raw_token, client)? {
+        return Ok(true);
+    }
+
+    let by_access = |conn: &mut PgConnection| {
+        sessions
+            .filter(token.eq(hash_token(raw_token)))
+            .select(family_id)
+            .first::<Uuid>(conn)
+            .optional()
+    };
+    let by_refresh = |conn: &mut PgConnection| {
+        find_refresh_token(conn, raw_token).map(|stored| stored.map(|t| t.family_id))
+    };
+
+    // The hint only decides which kind of token is looked up first
+    let family = if hint == Some("refresh_token") {
+        match by_refresh(conn)? {
+            Some(family) => Some(family),
+            None => by_access(conn)?,
+        }
+    } else {
+        match by_access(conn)? {
+            Some(family) => Some(family),
+            None => by_refresh(conn)?,
+        }
+    };
+
+    let Some(family) = family else {
+        return Ok(false);
+    };
+
+    // Another client's token is treated like an unknown one
+    if let Some(client) = client {
+        let owner = sessions
+            .filter(family_id.eq(family))
+            .select(session_client_id)
+            .first::<Option<String>>(conn)
+            .optional()?
+            .flatten();
+        if owner.as_deref() != Some(client) {
+            return Ok(false);
+        }
+    }
+
+    revoke_family(conn, family).map(|_| true)
 }
 
 
 pub async fn validate_refresh_token(refresh_token: &str) -> Result<TokenData<Claims>, String> {
-    let validation = Validation::default();
-    let jwt_secret = env::var("JWT_SECRET_X").expect("JWT_SECRET_X must be set");
-    match decode::<Claims>(refresh_token, &DecodingKey::from_secret(jwt_secret.as_ref()), &validation) {
+    let header = decode_header(refresh_token).map_err(|_| "Invalid Refresh token format")?;
+    let key = verification_key(header.kid.as_deref(), true)?;
+    match decode::<Claims>(refresh_token, &key.decoding, &validation(key.algorithm, true)) {
         Ok(token_data) => Ok(token_data),
         Err(err) => match *err.kind() {
             ErrorKind::ExpiredSignature => Err("Refresh Token has expired".to_string()),
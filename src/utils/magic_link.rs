@@ -0,0 +1,48 @@
+// src/utils/magic_link.rs
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use crate::models::{MagicLinkToken, NewMagicLinkToken, User};
+use crate::schema::magic_link_tokens::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+/// Creates a login token bound to the user's current email address.
+/// Returns the raw token with its expiry; only the hash is persisted.
+pub fn create_magic_link_token(
+    conn: &mut PgConnection,
+    user: &User,
+    ip: Option<String>,
+) -> QueryResult<(String, NaiveDateTime)> {
+    let raw_token = generate_token();
+    let expiration = Utc::now().naive_utc() + Duration::minutes(15);
+
+    diesel::insert_into(magic_link_tokens)
+        .values(&NewMagicLinkToken {
+            user_id: user.id,
+            email: user.email.clone(),
+            token_hash: hash_token(&raw_token),
+            expires_at: expiration,
+            requested_ip: ip,
+        })
+        .execute(conn)?;
+
+    Ok((raw_token, expiration))
+}
+
+/// Atomically marks an unused, unexpired token as used and returns it.
+pub fn consume_magic_link_token(
+    conn: &mut PgConnection,
+    raw_token: &str,
+) -> QueryResult<Option<MagicLinkToken>> {
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        magic_link_tokens
+            .filter(token_hash.eq(hash_token(raw_token)))
+            .filter(used_at.is_null())
+            .filter(expires_at.gt(now)),
+    )
+    .set(used_at.eq(now))
+    .get_result::<MagicLinkToken>(conn)
+    .optional()
+}
@@ -0,0 +1,69 @@
+// src/utils/mfa_challenge.rs
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use rand::Rng;
+use uuid::Uuid;
+use crate::models::{MfaChallenge, NewMfaChallenge};
+use crate::schema::mfa_challenges::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+pub const MAX_CHALLENGE_ATTEMPTS: i16 = 5;
+pub const CHALLENGE_LIFETIME_MINUTES: i64 = 5;
+
+/// Creates a short-lived challenge that stands in for the session until the
+/// second factor is verified. Returns the raw challenge token.
+/// `code` is the one-time code sent out of band, for factors that use one.
+pub fn create_challenge(conn: &mut PgConnection, owner_id: Uuid, code: Option<&str>) -> QueryResult<String> {
+    let raw_token = generate_token();
+
+    diesel::insert_into(mfa_challenges)
+        .values(&NewMfaChallenge {
+            user_id: owner_id,
+            token_hash: hash_token(&raw_token),
+            expires_at: Utc::now().naive_utc() + Duration::minutes(CHALLENGE_LIFETIME_MINUTES),
+            code_hash: code.map(hash_token),
+        })
+        .execute(conn)?;
+
+    Ok(raw_token)
+}
+
+/// Six digit numeric code for the email factor.
+pub fn generate_email_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+pub fn verify_email_code(challenge: &MfaChallenge, code: &str) -> bool {
+    challenge.code_hash.as_deref() == Some(hash_token(code.trim()).as_str())
+}
+
+/// Looks up an open challenge that has not run out of attempts.
+pub fn find_challenge(conn: &mut PgConnection, raw_token: &str) -> QueryResult<Option<MfaChallenge>> {
+    mfa_challenges
+        .filter(token_hash.eq(hash_token(raw_token)))
+        .filter(consumed_at.is_null())
+        .filter(expires_at.gt(Utc::now().naive_utc()))
+        .filter(attempts.lt(MAX_CHALLENGE_ATTEMPTS))
+        .first::<MfaChallenge>(conn)
+        .optional()
+}
+
+pub fn record_failed_attempt(conn: &mut PgConnection, challenge: &MfaChallenge) -> QueryResult<usize> {
+    diesel::update(mfa_challenges.filter(id.eq(challenge.id)))
+        .set(attempts.eq(attempts + 1))
+        .execute(conn)
+}
+
+/// Marks the challenge as used. Returns `false` if it had already been consumed.
+pub fn consume_challenge(conn: &mut PgConnection, challenge: &MfaChallenge) -> QueryResult<bool> {
+    let consumed = diesel::update(
+        mfa_challenges
+            .filter(id.eq(challenge.id))
+            .filter(consumed_at.is_null()),
+    )
+    .set(consumed_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+
+    Ok(consumed == 1)
+}
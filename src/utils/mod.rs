@@ -2,4 +2,24 @@ pub mod jwt_validator;
 pub mod jwt;
 pub(crate) mod gen_refresh_token;
 pub(crate) mod error;
-pub(crate) mod email;
\ No newline at end of file
+pub(crate) mod email;
+pub(crate) mod secure_token;
+pub(crate) mod reset_token;
+pub(crate) mod verification_token;
+pub(crate) mod totp;
+pub(crate) mod mfa_challenge;
+pub(crate) mod recovery_codes;
+pub(crate) mod webauthn;
+pub(crate) mod magic_link;
+pub(crate) mod permissions;
+pub(crate) mod organizations;
+pub(crate) mod invitation;
+pub(crate) mod key_encryption;
+pub(crate) mod signing_key;
+pub(crate) mod security_event;
+pub(crate) mod token_family;
+pub(crate) mod oauth_client;
+pub(crate) mod authorization_code;
+pub(crate) mod id_token;
+pub(crate) mod device_code;
+pub(crate) mod account_status;
@@ -0,0 +1,201 @@
+// src/utils/oauth_client.rs
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+use url::Url;
+use crate::models::{NewOAuthClient, NewOAuthClientToken, OAuthClient, OAuthClientToken};
+use crate::schema::oauth_client_tokens;
+use crate::schema::oauth_clients::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+/// What an admin registers a client with.
+pub struct ClientRegistration {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub allowed_scopes: Vec<String>,
+    /// Public clients (SPAs, native apps) cannot keep a secret and get none
+    pub public: bool,
+}
+
+/// Registers a client. Returns the stored client with the raw secret, if it
+/// has one; only the hash is persisted, so the secret cannot be shown again.
+pub fn register_client(
+    conn: &mut PgConnection,
+    registration: ClientRegistration,
+    creator: Uuid,
+) -> QueryResult<(OAuthClient, Option<String>)> {
+    let raw_secret = (!registration.public).then(generate_token);
+
+    let client = diesel::insert_into(oauth_clients)
+        .values(&NewOAuthClient {
+            client_id: generate_token()[..32].to_string(),
+            client_secret_hash: raw_secret.as_deref().map(hash_token),
+            name: registration.name,
+            created_by: Some(creator),
+            redirect_uris: registration.redirect_uris,
+            allowed_scopes: registration.allowed_scopes,
+        })
+        .get_result::<OAuthClient>(conn)?;
+
+    Ok((client, raw_secret))
+}
+
+pub fn find_client(conn: &mut PgConnection, id: &str) -> QueryResult<Option<OAuthClient>> {
+    oauth_clients
+        .find(id)
+        .first::<OAuthClient>(conn)
+        .optional()
+}
+
+/// Looks up the client and checks its secret. Public clients never pass.
+pub fn authenticate_client(
+    conn: &mut PgConnection,
+    id: &str,
+    secret: &str,
+) -> QueryResult<Option<OAuthClient>> {
+    let client = find_client(conn, id)?;
+
+    Ok(client.filter(|c| c.client_secret_hash.as_deref() == Some(hash_token(secret).as_str())))
+}
+
+pub fn is_public(client: &OAuthClient) -> bool {
+    client.client_secret_hash.is_none()
+}
+
+/// Redirect URIs are compared exactly, as registered.
+pub fn allows_redirect_uri(client: &OAuthClient, uri: &str) -> bool {
+    client.redirect_uris.iter().any(|registered| registered == uri)
+}
+
+/// Redirect URIs must be absolute, without a fragment, and use HTTPS unless
+/// they point back at the local machine.
+pub fn is_valid_redirect_uri(uri: &str) -> bool {
+    match Url::parse(uri) {
+        Ok(parsed) => {
+            let local = matches!(parsed.host_str(), Some("localhost") | Some("127.0.0.1") | Some("[::1]"));
+            parsed.fragment().is_none() && (parsed.scheme() == "https" || (parsed.scheme() == "http" && local))
+        },
+        Err(_) => false,
+    }
+}
+
+/// The scope granted for a request: everything the client is allowed when no
+/// scope is requested, or `None` if any requested scope is not allowed.
+pub fn grant_scope(client: &OAuthClient, requested: Option<&str>) -> Option<String> {
+    match requested {
+        Some(requested) => {
+            let scopes: Vec<&str> = requested.split_whitespace().collect();
+            scopes
+                .iter()
+                .all(|s| client.allowed_scopes.iter().any(|allowed| allowed == s))
+                .then(|| scopes.join(" "))
+        },
+        None => Some(client.allowed_scopes.join(" ")),
+    }
+}
+
+/// Stores the digest of a `client_credentials` access token so it can be
+/// checked and revoked like a session. Expired tokens of the same client are
+/// pruned on the way.
+pub fn record_client_token(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    raw_token: &str,
+    granted_scope: &str,
+    expires_at: NaiveDateTime,
+) -> QueryResult<OAuthClientToken> {
+    diesel::delete(
+        oauth_client_tokens::table
+            .filter(oauth_client_tokens::client_id.eq(&client.client_id))
+            .filter(oauth_client_tokens::expires_at.lt(Utc::now().naive_utc())),
+    )
+    .execute(conn)?;
+
+    diesel::insert_into(oauth_client_tokens::table)
+        .values(&NewOAuthClientToken {
+            client_id: client.client_id.clone(),
+            token_hash: hash_token(raw_token),
+            scope: granted_scope.to_string(),
+            expires_at,
+        })
+        .get_result::<OAuthClientToken>(conn)
+}
+
+/// The stored, unexpired `client_credentials` token, if any.
+pub fn find_client_token(conn: &mut PgConnection, raw_token: &str) -> QueryResult<Option<OAuthClientToken>> {
+    oauth_client_tokens::table
+        .filter(oauth_client_tokens::token_hash.eq(hash_token(raw_token)))
+        .filter(oauth_client_tokens::expires_at.gt(Utc::now().naive_utc()))
+        .first::<OAuthClientToken>(conn)
+        .optional()
+}
+
+/// Deletes a `client_credentials` token, if it was issued to `client` when
+/// one is given. Returns whether it was known.
+pub fn revoke_client_token(conn: &mut PgConnection, raw_token: &str, client: Option<&str>) -> QueryResult<bool> {
+    let mut query = diesel::delete(oauth_client_tokens::table)
+        .filter(oauth_client_tokens::token_hash.eq(hash_token(raw_token)))
+        .into_boxed();
+    if let Some(client) = client {
+        query = query.filter(oauth_client_tokens::client_id.eq(client));
+    }
+
+    query.execute(conn).map(|deleted| deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(scopes: &[&str]) -> OAuthClient {
+        OAuthClient {
+            client_id: "client".to_string(),
+            client_secret_hash: None,
+            name: "Client".to_string(),
+            created_by: None,
+            created_at: Utc::now().naive_utc(),
+            redirect_uris: vec!["https://app.example.com/callback".to_string()],
+            allowed_scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn grant_scope_defaults_to_every_allowed_scope() {
+        let client = client(&["openid", "email", "users:read"]);
+        assert_eq!(grant_scope(&client, None).as_deref(), Some("openid email users:read"));
+    }
+
+    #[test]
+    fn grant_scope_keeps_allowed_requests() {
+        let client = client(&["openid", "email", "users:read"]);
+        assert_eq!(grant_scope(&client, Some("openid  email")).as_deref(), Some("openid email"));
+    }
+
+    #[test]
+    fn grant_scope_refuses_scopes_outside_the_allow_list() {
+        let client = client(&["openid", "email"]);
+        assert_eq!(grant_scope(&client, Some("openid users:write")), None);
+    }
+
+    #[test]
+    fn redirect_uris_must_be_https_or_local() {
+        assert!(is_valid_redirect_uri("https://app.example.com/callback"));
+        assert!(is_valid_redirect_uri("http://localhost:8080/callback"));
+        assert!(is_valid_redirect_uri("http://127.0.0.1/callback"));
+        assert!(is_valid_redirect_uri("http://[::1]:3000/callback"));
+
+        assert!(!is_valid_redirect_uri("http://app.example.com/callback"));
+        assert!(!is_valid_redirect_uri("https://app.example.com/callback#fragment"));
+        assert!(!is_valid_redirect_uri("javascript:alert(1)"));
+        assert!(!is_valid_redirect_uri("/callback"));
+    }
+
+    #[test]
+    fn redirect_uris_match_exactly() {
+        let client = client(&[]);
+        assert!(allows_redirect_uri(&client, "https://app.example.com/callback"));
+        assert!(!allows_redirect_uri(&client, "https://app.example.com/callback/"));
+        assert!(!allows_redirect_uri(&client, "https://app.example.com/callback?next=/"));
+    }
+}
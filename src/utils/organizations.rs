@@ -0,0 +1,69 @@
+// src/utils/organizations.rs
+
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{NewOrganization, NewOrganizationMember, Organization, OrganizationMember};
+use crate::schema::{organization_members, organizations};
+
+pub const ORG_ROLES: [&str; 3] = ["owner", "admin", "member"];
+
+/// Owners and admins manage the members of their organization.
+pub fn can_manage_members(org_role: &str) -> bool {
+    org_role == "owner" || org_role == "admin"
+}
+
+pub fn find_membership(
+    conn: &mut PgConnection,
+    organization: Uuid,
+    member: Uuid,
+) -> QueryResult<Option<OrganizationMember>> {
+    organization_members::table
+        .find((organization, member))
+        .first::<OrganizationMember>(conn)
+        .optional()
+}
+
+/// The organization a user acts in unless they switch: the one they joined first.
+pub fn default_membership(conn: &mut PgConnection, member: Uuid) -> QueryResult<Option<OrganizationMember>> {
+    organization_members::table
+        .filter(organization_members::user_id.eq(member))
+        .order(organization_members::created_at.asc())
+        .first::<OrganizationMember>(conn)
+        .optional()
+}
+
+/// Adds the user to the organization. Existing memberships are left unchanged.
+pub fn add_member(conn: &mut PgConnection, organization: Uuid, member: Uuid, org_role: &str) -> QueryResult<usize> {
+    diesel::insert_into(organization_members::table)
+        .values(&NewOrganizationMember {
+            organization_id: organization,
+            user_id: member,
+            role: org_role.to_string(),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+/// Creates an organization owned by `owner`. A random suffix keeps the slug unique.
+pub fn create_organization(conn: &mut PgConnection, name: &str, owner: Uuid) -> QueryResult<Organization> {
+    let base: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let base = base.trim_matches('-');
+    let suffix = &hex::encode(rand::random::<[u8; 3]>());
+    let slug = if base.is_empty() {
+        suffix.to_string()
+    } else {
+        format!("{}-{}", &base[..base.len().min(40)], suffix)
+    };
+
+    conn.transaction(|conn| {
+        let organization = diesel::insert_into(organizations::table)
+            .values(&NewOrganization { name: name.to_string(), slug })
+            .get_result::<Organization>(conn)?;
+        add_member(conn, organization.id, owner, "owner")?;
+        Ok(organization)
+    })
+}
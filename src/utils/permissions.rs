@@ -0,0 +1,156 @@
+// src/utils/permissions.rs
+
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{NewUserRole, User};
+use crate::schema::{permissions, role_permissions, roles, user_roles};
+use crate::utils::organizations::{default_membership, find_membership};
+
+/// Everything a user is allowed to do, resolved once at login and embedded in
+/// the access token.
+#[derive(Clone, Debug)]
+pub struct Grants {
+    /// Primary role from `users.role`
+    pub role: String,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+    /// Active organization and the user's role in it
+    pub organization_id: Option<Uuid>,
+    pub org_role: Option<String>,
+    /// OAuth client the grants were delegated to, and the scope it was given
+    pub client_id: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl Grants {
+    /// Narrows the grants to what an OAuth client was given. Scopes that name
+    /// a permission delegate it; all other permissions are dropped. Roles are
+    /// never delegated, so role-guarded routes stay out of the client's reach.
+    pub fn for_client(mut self, client_id: &str, scope: &str) -> Grants {
+        let scopes: Vec<&str> = scope.split_whitespace().collect();
+        self.permissions.retain(|p| scopes.contains(&p.as_str()));
+        self.role = String::new();
+        self.roles.clear();
+        self.org_role = None;
+        self.client_id = Some(client_id.to_string());
+        self.scope = Some(scope.to_string());
+        self
+    }
+}
+
+/// The permissions named in a scope, for clients acting on their own behalf.
+pub fn scope_permissions(conn: &mut PgConnection, scope: &str) -> QueryResult<Vec<String>> {
+    permissions::table
+        .filter(permissions::name.eq_any(scope.split_whitespace()))
+        .select(permissions::name)
+        .order(permissions::name)
+        .load::<String>(conn)
+}
+
+/// Resolves the user's grants while acting in `organization`. Falls back to
+/// their default organization when none is given or they are no longer a member.
+pub fn resolve_grants(conn: &mut PgConnection, user: &User, organization: Option<Uuid>) -> QueryResult<Grants> {
+    let membership = match organization {
+        Some(org_id) => find_membership(conn, org_id, user.id)?,
+        None => None,
+    };
+    let membership = match membership {
+        Some(m) => Some(m),
+        None => default_membership(conn, user.id)?,
+    };
+
+    let mut role_names: Vec<String> = user_roles::table
+        .inner_join(roles::table)
+        .filter(user_roles::user_id.eq(user.id))
+        .select(roles::name)
+        .load(conn)?;
+    if !role_names.contains(&user.role) {
+        role_names.push(user.role.clone());
+    }
+    role_names.sort();
+
+    let permission_names: Vec<String> = role_permissions::table
+        .inner_join(roles::table)
+        .inner_join(permissions::table)
+        .filter(roles::name.eq_any(&role_names))
+        .select(permissions::name)
+        .distinct()
+        .order(permissions::name)
+        .load(conn)?;
+
+    Ok(Grants {
+        role: user.role.clone(),
+        roles: role_names,
+        permissions: permission_names,
+        organization_id: membership.as_ref().map(|m| m.organization_id),
+        org_role: membership.map(|m| m.role),
+        client_id: None,
+        scope: None,
+    })
+}
+
+/// Looks up the ids of the named roles. Returns `None` if any name is unknown.
+pub fn find_role_ids(conn: &mut PgConnection, names: &[String]) -> QueryResult<Option<Vec<i32>>> {
+    let found: Vec<(i32, String)> = roles::table
+        .filter(roles::name.eq_any(names))
+        .select((roles::id, roles::name))
+        .load(conn)?;
+
+    if names.iter().all(|n| found.iter().any(|(_, name)| name == n)) {
+        Ok(Some(found.into_iter().map(|(id, _)| id).collect()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Replaces the user's additional roles. The primary role is not affected.
+pub fn set_user_roles(conn: &mut PgConnection, owner_id: Uuid, role_ids: &[i32]) -> QueryResult<()> {
+    diesel::delete(user_roles::table.filter(user_roles::user_id.eq(owner_id)))
+        .execute(conn)?;
+
+    let memberships: Vec<NewUserRole> = role_ids
+        .iter()
+        .map(|&role_id| NewUserRole { user_id: owner_id, role_id })
+        .collect();
+    diesel::insert_into(user_roles::table)
+        .values(&memberships)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin_grants() -> Grants {
+        Grants {
+            role: "admin".to_string(),
+            roles: vec!["admin".to_string(), "user".to_string()],
+            permissions: vec!["users:read".to_string(), "users:write".to_string(), "users:delete".to_string()],
+            organization_id: Some(Uuid::new_v4()),
+            org_role: Some("owner".to_string()),
+            client_id: None,
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn for_client_keeps_only_scoped_permissions() {
+        let grants = admin_grants().for_client("client", "openid users:read");
+
+        assert_eq!(grants.permissions, vec!["users:read".to_string()]);
+        assert_eq!(grants.client_id.as_deref(), Some("client"));
+        assert_eq!(grants.scope.as_deref(), Some("openid users:read"));
+    }
+
+    #[test]
+    fn for_client_never_delegates_roles() {
+        let grants = admin_grants().for_client("client", "openid");
+
+        assert!(grants.permissions.is_empty());
+        assert!(grants.role.is_empty());
+        assert!(grants.roles.is_empty());
+        assert_eq!(grants.org_role, None);
+    }
+}
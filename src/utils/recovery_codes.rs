@@ -0,0 +1,111 @@
+// src/utils/recovery_codes.rs
+
+use chrono::Utc;
+use diesel::prelude::*;
+use rand::RngCore;
+use uuid::Uuid;
+use crate::models::NewMfaRecoveryCode;
+use crate::schema::mfa_recovery_codes::dsl::*;
+use crate::utils::secure_token::hash_token;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Codes are shown as `xxxxx-xxxxx` but accepted with any casing or separators
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// What is stored for a code, so any spelling of it matches the same row.
+fn code_digest(code: &str) -> String {
+    hash_token(&normalize_code(code))
+}
+
+fn generate_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Replaces the user's recovery codes with a fresh set and returns them.
+/// The codes are only ever shown here; just their hashes are stored.
+pub fn generate_recovery_codes(conn: &mut PgConnection, owner_id: Uuid) -> QueryResult<Vec<String>> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_code()).collect();
+
+    let new_codes: Vec<NewMfaRecoveryCode> = codes
+        .iter()
+        .map(|code| NewMfaRecoveryCode {
+            user_id: owner_id,
+            code_hash: code_digest(code),
+        })
+        .collect();
+
+    conn.transaction(|conn| {
+        diesel::delete(mfa_recovery_codes.filter(user_id.eq(owner_id))).execute(conn)?;
+        diesel::insert_into(mfa_recovery_codes).values(&new_codes).execute(conn)
+    })?;
+
+    Ok(codes)
+}
+
+/// Uses up a recovery code. Returns `false` if it is unknown or already used.
+pub fn consume_recovery_code(conn: &mut PgConnection, owner_id: Uuid, code: &str) -> QueryResult<bool> {
+    let consumed = diesel::update(
+        mfa_recovery_codes
+            .filter(user_id.eq(owner_id))
+            .filter(code_hash.eq(code_digest(code)))
+            .filter(used_at.is_null()),
+    )
+    .set(used_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+
+    Ok(consumed > 0)
+}
+
+pub fn count_remaining_codes(conn: &mut PgConnection, owner_id: Uuid) -> QueryResult<i64> {
+    mfa_recovery_codes
+        .filter(user_id.eq(owner_id))
+        .filter(used_at.is_null())
+        .count()
+        .get_result(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_codes_are_two_groups_of_five_hex_digits() {
+        for _ in 0..100 {
+            let code = generate_code();
+            let (first, second) = code.split_once('-').unwrap();
+
+            assert_eq!(first.len(), 5);
+            assert_eq!(second.len(), 5);
+            assert!(code.chars().all(|c| c == '-' || c.is_ascii_digit() || ('a'..='f').contains(&c)));
+        }
+    }
+
+    #[test]
+    fn normalization_ignores_case_and_separators() {
+        assert_eq!(normalize_code("ab12c-3de45"), "ab12c3de45");
+        assert_eq!(normalize_code("AB12C-3DE45"), "ab12c3de45");
+        assert_eq!(normalize_code(" ab12c 3de45 "), "ab12c3de45");
+        assert_eq!(normalize_code("ab-12c-3de-45"), "ab12c3de45");
+    }
+
+    #[test]
+    fn any_spelling_of_a_code_matches_its_digest() {
+        let code = generate_code();
+        let stored = code_digest(&code);
+
+        assert_eq!(code_digest(&code.to_ascii_uppercase()), stored);
+        assert_eq!(code_digest(&code.replace('-', "")), stored);
+        assert_eq!(code_digest(&code.replace('-', " ")), stored);
+        assert_ne!(code_digest(&generate_code()), stored);
+        assert_ne!(code_digest(&code[..10]), stored);
+    }
+}
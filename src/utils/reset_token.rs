@@ -0,0 +1,70 @@
+// src/utils/reset_token.rs
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{NewPasswordResetToken, PasswordResetToken};
+use crate::schema::password_reset_tokens::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+/// Creates a reset token for the user and returns the raw token with its expiry.
+/// Only the hash is persisted.
+pub fn create_reset_token(
+    conn: &mut PgConnection,
+    owner_id: Uuid,
+    ip: Option<String>,
+) -> QueryResult<(String, NaiveDateTime)> {
+    let raw_token = generate_token();
+    let expiration = Utc::now().naive_utc() + Duration::hours(1);
+
+    diesel::insert_into(password_reset_tokens)
+        .values(&NewPasswordResetToken {
+            user_id: owner_id,
+            token_hash: hash_token(&raw_token),
+            expires_at: expiration,
+            requested_ip: ip,
+        })
+        .execute(conn)?;
+
+    Ok((raw_token, expiration))
+}
+
+/// Looks up an unused, unexpired reset token by its raw value.
+pub fn find_reset_token(
+    conn: &mut PgConnection,
+    raw_token: &str,
+) -> QueryResult<Option<PasswordResetToken>> {
+    password_reset_tokens
+        .filter(token_hash.eq(hash_token(raw_token)))
+        .filter(used_at.is_null())
+        .filter(expires_at.gt(Utc::now().naive_utc()))
+        .first::<PasswordResetToken>(conn)
+        .optional()
+}
+
+/// Marks the token as used, along with any other outstanding tokens for the same user.
+/// Returns `false` if the token had already been consumed.
+pub fn consume_reset_token(
+    conn: &mut PgConnection,
+    reset_token: &PasswordResetToken,
+) -> QueryResult<bool> {
+    let now = Utc::now().naive_utc();
+
+    let consumed = diesel::update(
+        password_reset_tokens
+            .filter(id.eq(reset_token.id))
+            .filter(used_at.is_null()),
+    )
+    .set(used_at.eq(now))
+    .execute(conn)?;
+
+    diesel::update(
+        password_reset_tokens
+            .filter(user_id.eq(reset_token.user_id))
+            .filter(used_at.is_null()),
+    )
+    .set(used_at.eq(now))
+    .execute(conn)?;
+
+    Ok(consumed == 1)
+}
@@ -0,0 +1,16 @@
+// src/utils/secure_token.rs
+
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Generates a random, URL-safe opaque token.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// SHA-256 hex digest of a token, used for storing and looking it up.
+pub fn hash_token(raw_token: &str) -> String {
+    hex::encode(Sha256::digest(raw_token.as_bytes()))
+}
@@ -0,0 +1,26 @@
+// src/utils/security_event.rs
+
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::NewSecurityEvent;
+use crate::schema::security_events;
+
+pub const REFRESH_TOKEN_REUSE: &str = "refresh_token_reuse";
+pub const AUTHORIZATION_CODE_REUSE: &str = "authorization_code_reuse";
+
+/// Stores a security-relevant event for later review.
+pub fn record_security_event(
+    conn: &mut PgConnection,
+    user: Option<Uuid>,
+    event_type: &str,
+    details: String,
+) -> QueryResult<()> {
+    diesel::insert_into(security_events::table)
+        .values(&NewSecurityEvent {
+            user_id: user,
+            event_type: event_type.to_string(),
+            details: Some(details),
+        })
+        .execute(conn)?;
+    Ok(())
+}
@@ -0,0 +1,527 @@
+// src/utils/signing_key.rs
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::{env, fs};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use once_cell::sync::Lazy;
+use ring::aead::LessSafeKey;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use crate::db::PgPool;
+use crate::models::{JwtKey, NewJwtKey};
+use crate::schema::jwt_keys;
+use crate::utils::jwt::token_lifetime_minutes;
+use crate::utils::key_encryption::{encryption_key, is_encrypted, open, seal};
+
+pub const PURPOSES: [&str; 2] = ["access", "refresh"];
+pub const ALGORITHMS: [&str; 4] = ["HS256", "RS256", "ES256", "EdDSA"];
+
+/// Clients may cache the JWKS this long, so a new key is published at least
+/// this long before the schedule starts signing with it.
+pub const JWKS_MAX_AGE_SECONDS: i64 = 300;
+/// How often each instance re-reads the key table.
+const KEY_REFRESH_SECONDS: u64 = 60;
+
+/// A key tokens are signed with, named by the `kid` header of every token.
+pub struct SigningKey {
+    pub kid: String,
+    pub purpose: String,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    /// Public half published in the JWKS; `None` for HMAC keys
+    pub jwk: Option<Value>,
+}
+
+/// Keys in use: the active signing key per purpose, and every key that can
+/// still verify tokens (pending, active and retiring ones) by `kid`.
+struct KeySet {
+    signing: HashMap<String, Arc<SigningKey>>,
+    verifying: HashMap<String, Arc<SigningKey>>,
+}
+
+static KEYS: Lazy<RwLock<Option<Arc<KeySet>>>> = Lazy::new(|| RwLock::new(None));
+
+/// The keys loaded by `load_keys` and kept fresh by `run_key_schedule`.
+/// Requests never touch the database for them.
+fn key_set() -> Result<Arc<KeySet>, String> {
+    KEYS.read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .ok_or_else(|| "Signing keys are not loaded".to_string())
+}
+
+/// Seeds the key table if needed, encrypts keys stored before encryption
+/// existed and loads the keys. Run once on startup.
+pub fn load_keys(pool: &PgPool) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| format!("Database connection error: {}", e))?;
+    bootstrap_keys(&mut conn)?;
+    encrypt_stored_keys(&mut conn)?;
+    reload_keys(&mut conn)
+}
+
+/// Encrypts key material that is still stored in the clear.
+fn encrypt_stored_keys(conn: &mut PgConnection) -> Result<(), String> {
+    let encryption = encryption_key()?;
+    let rows = jwt_keys::table
+        .load::<JwtKey>(conn)
+        .map_err(|e| format!("Failed to load signing keys: {}", e))?;
+
+    for row in rows.iter().filter(|row| !is_encrypted(&row.private_key)) {
+        diesel::update(jwt_keys::table.find(row.id))
+            .set(jwt_keys::private_key.eq(seal(&encryption, &row.kid, &row.private_key)?))
+            .execute(conn)
+            .map_err(|e| format!("Failed to store signing key: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Re-reads the key table. The previous keys stay in use if that fails.
+fn reload_keys(conn: &mut PgConnection) -> Result<(), String> {
+    let set = load_key_set(conn)?;
+    *KEYS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(set));
+    Ok(())
+}
+
+fn load_key_set(conn: &mut PgConnection) -> Result<KeySet, String> {
+    let now = Utc::now().naive_utc();
+    let rows = jwt_keys::table
+        .filter(jwt_keys::status.ne("retired"))
+        .filter(jwt_keys::retire_after.is_null().or(jwt_keys::retire_after.gt(now)))
+        .load::<JwtKey>(conn)
+        .map_err(|e| format!("Failed to load signing keys: {}", e))?;
+
+    let encryption = encryption_key()?;
+    let mut signing = HashMap::new();
+    let mut verifying = HashMap::new();
+    for row in rows {
+        let key = Arc::new(parse_key(&encryption, &row)?);
+        if row.status == "active" {
+            signing.insert(row.purpose.clone(), key.clone());
+        }
+        verifying.insert(row.kid.clone(), key);
+    }
+
+    Ok(KeySet { signing, verifying })
+}
+
+/// The active key new tokens are signed with.
+pub fn signing_key(refresh: bool) -> Result<Arc<SigningKey>, String> {
+    let purpose = if refresh { "refresh" } else { "access" };
+    key_set()?
+        .signing
+        .get(purpose)
+        .cloned()
+        .ok_or_else(|| format!("No active {} signing key", purpose))
+}
+
+/// The key named by a token's `kid`. Tokens issued before key ids existed
+/// are checked against the active key.
+pub fn verification_key(kid: Option<&str>, refresh: bool) -> Result<Arc<SigningKey>, String> {
+    let Some(kid) = kid else {
+        return signing_key(refresh);
+    };
+    let purpose = if refresh { "refresh" } else { "access" };
+
+    key_set()?
+        .verifying
+        .get(kid)
+        .filter(|key| key.purpose == purpose)
+        .cloned()
+        .ok_or_else(|| "Unknown signing key".to_string())
+}
+
+/// The JSON Web Key Set other services use to verify access tokens. Pending
+/// keys are included so verifiers know them before they start signing.
+pub fn jwks() -> Value {
+    let mut keys: Vec<Value> = key_set()
+        .map(|set| {
+            set.verifying
+                .values()
+                .filter(|key| key.purpose == "access")
+                .filter_map(|key| key.jwk.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    keys.sort_by(|a, b| a["kid"].as_str().cmp(&b["kid"].as_str()));
+
+    json!({ "keys": keys })
+}
+
+fn parse_key(encryption: &LessSafeKey, row: &JwtKey) -> Result<SigningKey, String> {
+    let material = open(encryption, &row.kid, &row.private_key)?;
+    let key = match row.algorithm.as_str() {
+        "HS256" => hmac_key(&row.kid, &material),
+        algorithm => key_from_pem(algorithm, &material)?,
+    };
+    let mut key = with_kid(key, row.kid.clone());
+    key.purpose = row.purpose.clone();
+    Ok(key)
+}
+
+/// Seeds an empty key table from `JWT_SECRET` / `JWT_PRIVATE_KEY_PATH` and
+/// `JWT_SECRET_X` so tokens issued before rotation keep verifying. Without
+/// them a fresh key is generated.
+fn bootstrap_keys(conn: &mut PgConnection) -> Result<(), String> {
+    let encryption = encryption_key()?;
+    for purpose in PURPOSES {
+        let exists = diesel::select(diesel::dsl::exists(
+            jwt_keys::table.filter(jwt_keys::purpose.eq(purpose)),
+        ))
+        .get_result::<bool>(conn)
+        .map_err(|e| format!("Failed to load signing keys: {}", e))?;
+        if exists {
+            continue;
+        }
+
+        let (algorithm, private_key, kid) = match env_key(purpose)? {
+            Some(found) => found,
+            None => {
+                let algorithm = env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string());
+                let algorithm = if purpose == "refresh" { "HS256".to_string() } else { algorithm };
+                let (private_key, kid) = generate_material(&algorithm)?;
+                (algorithm, private_key, kid)
+            },
+        };
+
+        // Another instance may be bootstrapping at the same time
+        diesel::insert_into(jwt_keys::table)
+            .values(&NewJwtKey {
+                private_key: seal(&encryption, &kid, &private_key)?,
+                kid,
+                purpose: purpose.to_string(),
+                algorithm,
+                status: "active".to_string(),
+                activated_at: Some(Utc::now().naive_utc()),
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map_err(|e| format!("Failed to store signing key: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// The key configured through the environment, as `(algorithm, private key, kid)`.
+fn env_key(purpose: &str) -> Result<Option<(String, String, String)>, String> {
+    if purpose == "refresh" {
+        return Ok(env::var("JWT_SECRET_X")
+            .ok()
+            .map(|secret| ("HS256".to_string(), secret, "refresh".to_string())));
+    }
+
+    let algorithm = env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string());
+    let key_id = env::var("JWT_KEY_ID").ok();
+
+    match algorithm.as_str() {
+        "HS256" => Ok(env::var("JWT_SECRET")
+            .ok()
+            .map(|secret| (algorithm.clone(), secret, key_id.unwrap_or_else(|| "access".to_string())))),
+        "RS256" | "ES256" | "EdDSA" => {
+            let Ok(path) = env::var("JWT_PRIVATE_KEY_PATH") else {
+                return Ok(None);
+            };
+            let pem = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read signing key {}: {}", path, e))?;
+            let kid = match key_id {
+                Some(kid) => kid,
+                None => key_from_pem(&algorithm, &pem)?.kid,
+            };
+            Ok(Some((algorithm, pem, kid)))
+        },
+        other => Err(format!("Unsupported JWT_ALGORITHM: {}", other)),
+    }
+}
+
+/// Generates new key material, returning it with its key id.
+fn generate_material(algorithm: &str) -> Result<(String, String), String> {
+    use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to generate {} key: {}", algorithm, e);
+
+    let pem = match algorithm {
+        "HS256" => {
+            let secret = hex::encode(rand::random::<[u8; 32]>());
+            let kid = hex::encode(rand::random::<[u8; 8]>());
+            return Ok((secret, kid));
+        },
+        "RS256" => rsa::RsaPrivateKey::new(&mut rand::thread_rng(), 2048)
+            .map_err(|e| failed(&e))?
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| failed(&e))?
+            .to_string(),
+        "ES256" => p256::SecretKey::random(&mut rand::thread_rng())
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| failed(&e))?
+            .to_string(),
+        "EdDSA" => ed25519_dalek::SigningKey::from_bytes(&rand::random())
+            .to_pkcs8_pem(LineEnding::LF)
+            .map_err(|e| failed(&e))?
+            .to_string(),
+        other => return Err(format!("Unsupported algorithm: {}", other)),
+    };
+
+    let kid = key_from_pem(algorithm, &pem)?.kid;
+    Ok((pem, kid))
+}
+
+/// Adds a pending key. It verifies tokens and appears in the JWKS right away,
+/// but only signs once promoted.
+pub fn create_key(conn: &mut PgConnection, purpose: &str, algorithm: &str) -> Result<JwtKey, String> {
+    if !PURPOSES.contains(&purpose) {
+        return Err(format!("Invalid purpose: {}", purpose));
+    }
+    if !ALGORITHMS.contains(&algorithm) {
+        return Err(format!("Unsupported algorithm: {}", algorithm));
+    }
+
+    let (private_key, kid) = generate_material(algorithm)?;
+    let key = diesel::insert_into(jwt_keys::table)
+        .values(&NewJwtKey {
+            private_key: seal(&encryption_key()?, &kid, &private_key)?,
+            kid,
+            purpose: purpose.to_string(),
+            algorithm: algorithm.to_string(),
+            status: "pending".to_string(),
+            activated_at: None,
+        })
+        .get_result::<JwtKey>(conn)
+        .map_err(|e| format!("Failed to store signing key: {}", e))?;
+
+    reload_keys(conn)?;
+    Ok(key)
+}
+
+/// Makes a pending key the signing key of its purpose. The previous one keeps
+/// verifying until every token it signed has expired.
+pub fn promote_key(conn: &mut PgConnection, key_id: &str) -> Result<JwtKey, String> {
+    let now = Utc::now().naive_utc();
+
+    let promoted = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let Some(pending) = jwt_keys::table
+            .filter(jwt_keys::kid.eq(key_id))
+            .filter(jwt_keys::status.eq("pending"))
+            .for_update()
+            .first::<JwtKey>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        // One minute of slack for clock skew between instances
+        let lifetime = token_lifetime_minutes(pending.purpose == "refresh") + 1;
+        diesel::update(
+            jwt_keys::table
+                .filter(jwt_keys::purpose.eq(&pending.purpose))
+                .filter(jwt_keys::status.eq("active")),
+        )
+        .set((
+            jwt_keys::status.eq("retiring"),
+            jwt_keys::retire_after.eq(now + Duration::minutes(lifetime)),
+        ))
+        .execute(conn)?;
+
+        diesel::update(jwt_keys::table.find(pending.id))
+            .set((
+                jwt_keys::status.eq("active"),
+                jwt_keys::activated_at.eq(now),
+            ))
+            .get_result::<JwtKey>(conn)
+            .map(Some)
+    })
+    .map_err(|e| format!("Failed to promote signing key: {}", e))?
+    .ok_or_else(|| "No pending key with that id".to_string())?;
+
+    reload_keys(conn)?;
+    Ok(promoted)
+}
+
+/// Marks retiring keys whose tokens have all expired as retired.
+pub fn retire_expired_keys(conn: &mut PgConnection) -> QueryResult<usize> {
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        jwt_keys::table
+            .filter(jwt_keys::status.eq("retiring"))
+            .filter(jwt_keys::retire_after.le(now)),
+    )
+    .set((
+        jwt_keys::status.eq("retired"),
+        jwt_keys::retired_at.eq(now),
+    ))
+    .execute(conn)
+}
+
+/// One step of the rotation schedule: retires expired keys, and once the
+/// active key is older than `rotation_days` publishes a successor and
+/// promotes it after the JWKS cache time has passed.
+fn rotate_due_keys(conn: &mut PgConnection, rotation_days: i64) -> Result<(), String> {
+    retire_expired_keys(conn).map_err(|e| format!("Failed to retire signing keys: {}", e))?;
+
+    let now = Utc::now().naive_utc();
+    for purpose in PURPOSES {
+        let active = jwt_keys::table
+            .filter(jwt_keys::purpose.eq(purpose))
+            .filter(jwt_keys::status.eq("active"))
+            .first::<JwtKey>(conn)
+            .optional()
+            .map_err(|e| format!("Failed to load signing keys: {}", e))?;
+        let Some(active) = active else { continue };
+        if active.activated_at.unwrap_or(active.created_at) + Duration::days(rotation_days) > now {
+            continue;
+        }
+
+        let pending = jwt_keys::table
+            .filter(jwt_keys::purpose.eq(purpose))
+            .filter(jwt_keys::status.eq("pending"))
+            .order(jwt_keys::created_at.desc())
+            .first::<JwtKey>(conn)
+            .optional()
+            .map_err(|e| format!("Failed to load signing keys: {}", e))?;
+
+        match pending {
+            Some(next) if next.created_at + Duration::seconds(JWKS_MAX_AGE_SECONDS) <= now => {
+                promote_key(conn, &next.kid)?;
+            },
+            Some(_) => {},
+            None => {
+                create_key(conn, purpose, &active.algorithm)?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// One tick of the key schedule: rotates keys when `JWT_KEY_ROTATION_DAYS` is
+/// set, then picks up keys other instances created or promoted.
+fn refresh_keys(pool: &PgPool, rotation_days: Option<i64>) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| format!("Database connection error: {}", e))?;
+    if let Some(rotation_days) = rotation_days {
+        rotate_due_keys(&mut conn, rotation_days)?;
+    }
+    reload_keys(&mut conn)
+}
+
+/// Keeps the loaded keys fresh. A failed tick keeps the previous keys and is
+/// retried on the next one.
+pub async fn run_key_schedule(pool: PgPool, rotation_days: Option<i64>) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(KEY_REFRESH_SECONDS));
+    // The first tick completes immediately and the keys were just loaded
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+
+        let pool = pool.clone();
+        let _ = tokio::task::spawn_blocking(move || refresh_keys(&pool, rotation_days)).await;
+    }
+}
+
+fn hmac_key(kid: &str, secret: &str) -> SigningKey {
+    SigningKey {
+        kid: kid.to_string(),
+        purpose: String::new(),
+        algorithm: Algorithm::HS256,
+        encoding: EncodingKey::from_secret(secret.as_ref()),
+        decoding: DecodingKey::from_secret(secret.as_ref()),
+        jwk: None,
+    }
+}
+
+fn with_kid(mut key: SigningKey, kid: String) -> SigningKey {
+    if let Some(Value::Object(jwk)) = key.jwk.as_mut() {
+        jwk.insert("kid".to_string(), Value::String(kid.clone()));
+    }
+    key.kid = kid;
+    key
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// RFC 7638 thumbprint over the required members, in lexicographic order.
+fn thumbprint(canonical: String) -> String {
+    b64(&Sha256::digest(canonical.as_bytes()))
+}
+
+/// Loads an asymmetric private key and derives its public JWK. The key id
+/// defaults to the JWK thumbprint.
+fn key_from_pem(algorithm: &str, pem: &str) -> Result<SigningKey, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid {} signing key: {}", algorithm, e);
+
+    let (alg, encoding, decoding, kid, jwk) = match algorithm {
+        "RS256" => {
+            use rsa::pkcs1::DecodeRsaPrivateKey;
+            use rsa::pkcs8::DecodePrivateKey;
+            use rsa::traits::PublicKeyParts;
+
+            let private = rsa::RsaPrivateKey::from_pkcs8_pem(pem)
+                .or_else(|_| rsa::RsaPrivateKey::from_pkcs1_pem(pem))
+                .map_err(|e| invalid(&e))?;
+            let n = private.n().to_bytes_be();
+            let e = private.e().to_bytes_be();
+
+            let kid = thumbprint(format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, b64(&e), b64(&n)));
+            let jwk = json!({ "kty": "RSA", "n": b64(&n), "e": b64(&e) });
+            (
+                Algorithm::RS256,
+                EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| invalid(&e))?,
+                DecodingKey::from_rsa_raw_components(&n, &e),
+                kid,
+                jwk,
+            )
+        },
+        "ES256" => {
+            use p256::elliptic_curve::sec1::ToEncodedPoint;
+            use p256::pkcs8::DecodePrivateKey;
+
+            let private = p256::SecretKey::from_pkcs8_pem(pem).map_err(|e| invalid(&e))?;
+            let point = private.public_key().to_encoded_point(false);
+            let x = b64(point.x().ok_or_else(|| invalid(&"missing x coordinate"))?);
+            let y = b64(point.y().ok_or_else(|| invalid(&"missing y coordinate"))?);
+
+            let kid = thumbprint(format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y));
+            let decoding = DecodingKey::from_ec_components(&x, &y).map_err(|e| invalid(&e))?;
+            let jwk = json!({ "kty": "EC", "crv": "P-256", "x": x, "y": y });
+            (
+                Algorithm::ES256,
+                EncodingKey::from_ec_pem(pem.as_bytes()).map_err(|e| invalid(&e))?,
+                decoding,
+                kid,
+                jwk,
+            )
+        },
+        "EdDSA" => {
+            use ed25519_dalek::pkcs8::DecodePrivateKey;
+
+            let private = ed25519_dalek::SigningKey::from_pkcs8_pem(pem).map_err(|e| invalid(&e))?;
+            let x = b64(private.verifying_key().as_bytes());
+
+            let kid = thumbprint(format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, x));
+            let decoding = DecodingKey::from_ed_components(&x).map_err(|e| invalid(&e))?;
+            let jwk = json!({ "kty": "OKP", "crv": "Ed25519", "x": x });
+            (
+                Algorithm::EdDSA,
+                EncodingKey::from_ed_pem(pem.as_bytes()).map_err(|e| invalid(&e))?,
+                decoding,
+                kid,
+                jwk,
+            )
+        },
+        other => return Err(format!("Unsupported algorithm: {}", other)),
+    };
+
+    let mut jwk = jwk;
+    jwk["kid"] = Value::String(kid.clone());
+    jwk["alg"] = Value::String(algorithm.to_string());
+    jwk["use"] = Value::String("sig".to_string());
+
+    Ok(SigningKey { kid, purpose: String::new(), algorithm: alg, encoding, decoding, jwk: Some(jwk) })
+}
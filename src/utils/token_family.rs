@@ -0,0 +1,70 @@
+// src/utils/token_family.rs
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{NewRefreshToken, RefreshToken};
+use crate::schema::refresh_tokens::dsl::*;
+use crate::schema::sessions;
+use crate::utils::jwt::token_lifetime_minutes;
+use crate::utils::secure_token::hash_token;
+
+/// Records a refresh token issued in `family`. Tokens of the family that have
+/// expired are dropped, since they can no longer be presented anyway.
+pub fn record_refresh_token(
+    conn: &mut PgConnection,
+    family: Uuid,
+    owner: Uuid,
+    raw_token: &str,
+) -> QueryResult<()> {
+    let cutoff = Utc::now().naive_utc() - Duration::minutes(token_lifetime_minutes(true));
+    diesel::delete(refresh_tokens.filter(family_id.eq(family)).filter(created_at.lt(cutoff)))
+        .execute(conn)?;
+
+    diesel::insert_into(refresh_tokens)
+        .values(&NewRefreshToken {
+            family_id: family,
+            user_id: owner,
+            token_hash: hash_token(raw_token),
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn find_refresh_token(conn: &mut PgConnection, raw_token: &str) -> QueryResult<Option<RefreshToken>> {
+    refresh_tokens
+        .filter(token_hash.eq(hash_token(raw_token)))
+        .first::<RefreshToken>(conn)
+        .optional()
+}
+
+/// A token is only good for one rotation; anything after that is a replay.
+pub fn is_spent(stored: &RefreshToken) -> bool {
+    stored.rotated_at.is_some() || stored.revoked_at.is_some()
+}
+
+/// Marks the token as exchanged. Returns `false` if another request got there
+/// first, which has to be treated as reuse.
+pub fn mark_rotated(conn: &mut PgConnection, stored: &RefreshToken) -> QueryResult<bool> {
+    let updated = diesel::update(
+        refresh_tokens
+            .find(stored.id)
+            .filter(rotated_at.is_null())
+            .filter(revoked_at.is_null()),
+    )
+    .set(rotated_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+    Ok(updated == 1)
+}
+
+/// Ends every session of the family and invalidates all of its refresh tokens.
+pub fn revoke_family(conn: &mut PgConnection, family: Uuid) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        diesel::update(refresh_tokens.filter(family_id.eq(family)).filter(revoked_at.is_null()))
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+
+        diesel::delete(sessions::table.filter(sessions::family_id.eq(family)))
+            .execute(conn)
+    })
+}
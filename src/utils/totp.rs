@@ -0,0 +1,115 @@
+// src/utils/totp.rs
+
+use std::env;
+use chrono::Utc;
+use totp_rs::{Algorithm, Secret, TOTP};
+use crate::utils::error::AppError;
+
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+// Number of steps before and after the current one that are still accepted
+const TOTP_SKEW: i64 = 1;
+
+pub fn generate_totp_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+pub fn build_totp(secret: &str, account_name: &str) -> Result<TOTP, AppError> {
+    let issuer = env::var("MFA_ISSUER").unwrap_or_else(|_| "Rusted-Lock".to_string());
+    let secret_bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::InternalServerError(format!("Invalid TOTP secret: {:?}", e)))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW as u8,
+        TOTP_STEP,
+        secret_bytes,
+        Some(issuer),
+        account_name.to_string(),
+    ).map_err(|e| AppError::InternalServerError(format!("Failed to build TOTP: {}", e)))
+}
+
+/// Checks a code against the current time window and returns the matched time step.
+/// Steps at or before `last_used_step` are rejected so a code can only be used once.
+pub fn verify_totp_code(totp: &TOTP, code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    verify_totp_code_at(totp, code, last_used_step, Utc::now().timestamp())
+}
+
+fn verify_totp_code_at(totp: &TOTP, code: &str, last_used_step: Option<i64>, now: i64) -> Option<i64> {
+    let current_step = now / TOTP_STEP as i64;
+
+    (-TOTP_SKEW..=TOTP_SKEW)
+        .map(|offset| current_step + offset)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp.generate(*step as u64 * TOTP_STEP) == code.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RFC 6238 SHA-1 seed "12345678901234567890", base32 encoded
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn rfc_totp() -> TOTP {
+        build_totp(RFC_SECRET, "alice@example.com").unwrap()
+    }
+
+    #[test]
+    fn accepts_rfc_6238_test_vectors() {
+        // Appendix B lists eight digits; six-digit codes are their last six
+        let vectors = [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+            (20000000000, "353130"),
+        ];
+        let totp = rfc_totp();
+
+        for (time, code) in vectors {
+            assert_eq!(
+                verify_totp_code_at(&totp, code, None, time),
+                Some(time / TOTP_STEP as i64),
+                "code at {}",
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_one_step_either_side() {
+        let totp = rfc_totp();
+
+        let time = 1234567890;
+        let step = time / TOTP_STEP as i64;
+
+        assert_eq!(verify_totp_code_at(&totp, "005924", None, time + 30), Some(step));
+        assert_eq!(verify_totp_code_at(&totp, "005924", None, time - 30), Some(step));
+        assert_eq!(verify_totp_code_at(&totp, "005924", None, time + 60), None);
+        assert_eq!(verify_totp_code_at(&totp, "005924", None, time - 60), None);
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        let totp = rfc_totp();
+
+        for code in ["", "28708", "2870820", "abcdef", "287 082"] {
+            assert_eq!(verify_totp_code_at(&totp, code, None, 59), None, "code {:?}", code);
+        }
+        assert_eq!(verify_totp_code_at(&totp, " 287082 ", None, 59), Some(1));
+    }
+
+    #[test]
+    fn rejects_reused_codes() {
+        let totp = rfc_totp();
+        let step = verify_totp_code_at(&totp, "287082", None, 59).unwrap();
+
+        assert_eq!(verify_totp_code_at(&totp, "287082", Some(step), 59), None);
+        assert_eq!(verify_totp_code_at(&totp, "287082", Some(step + 1), 59 + 30), None);
+        assert_eq!(verify_totp_code_at(&totp, "287082", Some(step - 1), 59), Some(step));
+    }
+}
@@ -0,0 +1,79 @@
+// src/utils/verification_token.rs
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+use crate::models::{EmailVerificationToken, NewEmailVerificationToken, User};
+use crate::schema::email_verification_tokens::dsl::*;
+use crate::utils::secure_token::{generate_token, hash_token};
+
+/// Creates a verification token for the user's current email address,
+/// replacing any outstanding one. Returns the raw token with its expiry; only
+/// the hash is persisted.
+pub fn create_verification_token(
+    conn: &mut PgConnection,
+    user: &User,
+) -> QueryResult<(String, NaiveDateTime)> {
+    let raw_token = generate_token();
+    let now = Utc::now().naive_utc();
+    let expiration = now + Duration::hours(24);
+
+    diesel::update(
+        email_verification_tokens
+            .filter(user_id.eq(user.id))
+            .filter(used_at.is_null()),
+    )
+    .set(used_at.eq(now))
+    .execute(conn)?;
+
+    diesel::insert_into(email_verification_tokens)
+        .values(&NewEmailVerificationToken {
+            user_id: user.id,
+            token_hash: hash_token(&raw_token),
+            expires_at: expiration,
+            email: user.email.clone(),
+        })
+        .execute(conn)?;
+
+    Ok((raw_token, expiration))
+}
+
+/// Deletes the user's unused tokens, e.g. because they were sent to an
+/// address the account no longer has.
+pub fn delete_verification_tokens(conn: &mut PgConnection, owner_id: Uuid) -> QueryResult<usize> {
+    diesel::delete(
+        email_verification_tokens
+            .filter(user_id.eq(owner_id))
+            .filter(used_at.is_null()),
+    )
+    .execute(conn)
+}
+
+/// Looks up an unused, unexpired verification token by its raw value.
+pub fn find_verification_token(
+    conn: &mut PgConnection,
+    raw_token: &str,
+) -> QueryResult<Option<EmailVerificationToken>> {
+    email_verification_tokens
+        .filter(token_hash.eq(hash_token(raw_token)))
+        .filter(used_at.is_null())
+        .filter(expires_at.gt(Utc::now().naive_utc()))
+        .first::<EmailVerificationToken>(conn)
+        .optional()
+}
+
+/// Marks the token as used. Returns `false` if it had already been consumed.
+pub fn consume_verification_token(
+    conn: &mut PgConnection,
+    verification_token: &EmailVerificationToken,
+) -> QueryResult<bool> {
+    let consumed = diesel::update(
+        email_verification_tokens
+            .filter(id.eq(verification_token.id))
+            .filter(used_at.is_null()),
+    )
+    .set(used_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+
+    Ok(consumed == 1)
+}
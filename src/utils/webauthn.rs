@@ -0,0 +1,403 @@
+// src/utils/webauthn.rs
+//
+// Minimal WebAuthn relying party support for ES256 (P-256) credentials.
+// Registrations request `none` attestation, so attestation statements are not verified.
+
+use std::env;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use ciborium::value::Value;
+use diesel::prelude::*;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{DerSignature, VerifyingKey};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::models::{NewWebauthnChallenge, WebauthnChallenge};
+use crate::schema::webauthn_challenges;
+use crate::utils::error::AppError;
+
+// COSE algorithm identifier for ECDSA with SHA-256 on P-256
+pub const COSE_ALG_ES256: i64 = -7;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+pub fn rp_id() -> String {
+    env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string())
+}
+
+pub fn rp_name() -> String {
+    env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "Rusted-Lock".to_string())
+}
+
+fn rp_origin() -> String {
+    env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| "http://localhost:3000".to_string())
+}
+
+fn invalid(message: &str) -> AppError {
+    AppError::ValidationError(message.to_string())
+}
+
+pub fn encode_b64url(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn decode_b64url(value: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| invalid("Invalid base64url value"))
+}
+
+/// Stores a fresh challenge for a registration or authentication ceremony and returns it.
+pub fn create_challenge(conn: &mut PgConnection, owner_id: Option<Uuid>, ceremony: &str) -> QueryResult<String> {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let challenge = encode_b64url(&bytes);
+
+    diesel::insert_into(webauthn_challenges::table)
+        .values(&NewWebauthnChallenge {
+            user_id: owner_id,
+            challenge: challenge.clone(),
+            ceremony: ceremony.to_string(),
+            expires_at: Utc::now().naive_utc() + Duration::minutes(5),
+        })
+        .execute(conn)?;
+
+    Ok(challenge)
+}
+
+/// Consumes an open challenge so it can only be answered once.
+pub fn take_challenge(conn: &mut PgConnection, challenge: &str, ceremony: &str) -> Result<WebauthnChallenge, AppError> {
+    let now = Utc::now().naive_utc();
+
+    diesel::update(
+        webauthn_challenges::table
+            .filter(webauthn_challenges::challenge.eq(challenge))
+            .filter(webauthn_challenges::ceremony.eq(ceremony))
+            .filter(webauthn_challenges::consumed_at.is_null())
+            .filter(webauthn_challenges::expires_at.gt(now)),
+    )
+    .set(webauthn_challenges::consumed_at.eq(now))
+    .get_result::<WebauthnChallenge>(conn)
+    .optional()?
+    .ok_or_else(|| invalid("Invalid or expired WebAuthn challenge"))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ClientData {
+    #[serde(rename = "type")]
+    pub ceremony_type: String,
+    pub challenge: String,
+    pub origin: String,
+}
+
+/// Parses `clientDataJSON` and checks the ceremony type and origin.
+/// The caller matches the returned challenge against a stored one.
+pub fn parse_client_data(client_data_json: &[u8], expected_type: &str) -> Result<ClientData, AppError> {
+    let client_data: ClientData = serde_json::from_slice(client_data_json)
+        .map_err(|_| invalid("Invalid client data"))?;
+
+    if client_data.ceremony_type != expected_type {
+        return Err(invalid("Unexpected client data type"));
+    }
+    if client_data.origin != rp_origin() {
+        return Err(invalid("Unexpected origin"));
+    }
+
+    Ok(client_data)
+}
+
+pub struct AttestedCredential {
+    pub credential_id: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+pub struct AuthenticatorData {
+    pub sign_count: u32,
+    pub attested_credential: Option<AttestedCredential>,
+}
+
+/// Parses authenticator data, checking the RP id hash and that the user was
+/// both present and verified.
+pub fn parse_authenticator_data(data: &[u8]) -> Result<AuthenticatorData, AppError> {
+    if data.len() < 37 {
+        return Err(invalid("Authenticator data too short"));
+    }
+
+    if data[..32] != Sha256::digest(rp_id().as_bytes())[..] {
+        return Err(invalid("Unexpected relying party"));
+    }
+
+    let flags = data[32];
+    if flags & FLAG_USER_PRESENT == 0 || flags & FLAG_USER_VERIFIED == 0 {
+        return Err(invalid("User presence and verification are required"));
+    }
+
+    let sign_count = u32::from_be_bytes([data[33], data[34], data[35], data[36]]);
+
+    let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+        // aaguid (16 bytes) followed by a 2 byte credential id length
+        let rest = &data[37..];
+        if rest.len() < 18 {
+            return Err(invalid("Attested credential data too short"));
+        }
+        let id_len = u16::from_be_bytes([rest[16], rest[17]]) as usize;
+        if rest.len() < 18 + id_len {
+            return Err(invalid("Attested credential data too short"));
+        }
+        let credential_id = rest[18..18 + id_len].to_vec();
+        let cose_key: Value = ciborium::de::from_reader(&rest[18 + id_len..])
+            .map_err(|_| invalid("Invalid credential public key"))?;
+
+        Some(AttestedCredential {
+            credential_id,
+            public_key: cose_key_to_sec1(&cose_key)?,
+        })
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData { sign_count, attested_credential })
+}
+
+fn cose_param(key: &[(Value, Value)], label: i64) -> Option<&Value> {
+    key.iter()
+        .find(|(k, _)| k.as_integer().map(i128::from) == Some(label as i128))
+        .map(|(_, v)| v)
+}
+
+// Converts an EC2 / P-256 COSE key into an uncompressed SEC1 point
+fn cose_key_to_sec1(cose_key: &Value) -> Result<Vec<u8>, AppError> {
+    let key = cose_key.as_map().ok_or_else(|| invalid("Invalid credential public key"))?;
+    let int_param = |label| cose_param(key, label)
+        .and_then(|v| v.as_integer())
+        .map(i128::from);
+    let bytes_param = |label| cose_param(key, label)
+        .and_then(|v| v.as_bytes())
+        .filter(|b| b.len() == 32);
+
+    // kty = EC2, alg = ES256, crv = P-256
+    if int_param(1) != Some(2) || int_param(3) != Some(COSE_ALG_ES256 as i128) || int_param(-1) != Some(1) {
+        return Err(invalid("Only ES256 credentials are supported"));
+    }
+
+    let (x, y) = bytes_param(-2)
+        .zip(bytes_param(-3))
+        .ok_or_else(|| invalid("Invalid credential public key"))?;
+
+    let mut point = Vec::with_capacity(65);
+    point.push(0x04);
+    point.extend_from_slice(x);
+    point.extend_from_slice(y);
+
+    VerifyingKey::from_sec1_bytes(&point).map_err(|_| invalid("Invalid credential public key"))?;
+
+    Ok(point)
+}
+
+/// Extracts `authData` from a CBOR encoded attestation object.
+pub fn parse_attestation_object(attestation_object: &[u8]) -> Result<Vec<u8>, AppError> {
+    let value: Value = ciborium::de::from_reader(attestation_object)
+        .map_err(|_| invalid("Invalid attestation object"))?;
+
+    value.as_map()
+        .and_then(|map| map.iter().find(|(k, _)| k.as_text() == Some("authData")))
+        .and_then(|(_, v)| v.as_bytes())
+        .cloned()
+        .ok_or_else(|| invalid("Invalid attestation object"))
+}
+
+/// A counter that does not move forward suggests a cloned authenticator.
+/// Authenticators without a counter always report zero.
+pub fn check_sign_count(stored: i64, presented: u32) -> Result<(), AppError> {
+    let presented = presented as i64;
+    if (presented != 0 || stored != 0) && presented <= stored {
+        return Err(invalid("Credential counter check failed"));
+    }
+    Ok(())
+}
+
+/// Verifies an assertion signature over `authenticatorData || SHA-256(clientDataJSON)`.
+pub fn verify_assertion_signature(
+    public_key: &[u8],
+    authenticator_data: &[u8],
+    client_data_json: &[u8],
+    signature: &[u8],
+) -> Result<(), AppError> {
+    let verifying_key = VerifyingKey::from_sec1_bytes(public_key)
+        .map_err(|_| invalid("Invalid credential public key"))?;
+    let signature = DerSignature::try_from(signature)
+        .map_err(|_| invalid("Invalid signature"))?;
+
+    let mut signed_data = authenticator_data.to_vec();
+    signed_data.extend_from_slice(&Sha256::digest(client_data_json));
+
+    verifying_key
+        .verify(&signed_data, &signature)
+        .map_err(|_| invalid("Invalid signature"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::{Signature, SigningKey};
+
+    const CREDENTIAL_ID: &[u8] = b"software-authenticator";
+
+    /// A software authenticator holding a fixed P-256 key.
+    struct SoftwareAuthenticator {
+        key: SigningKey,
+        sign_count: u32,
+    }
+
+    impl SoftwareAuthenticator {
+        fn new() -> Self {
+            SoftwareAuthenticator {
+                key: SigningKey::from_slice(&[7u8; 32]).unwrap(),
+                sign_count: 0,
+            }
+        }
+
+        fn public_key(&self) -> Vec<u8> {
+            VerifyingKey::from(&self.key).to_encoded_point(false).as_bytes().to_vec()
+        }
+
+        fn cose_key(&self) -> Vec<u8> {
+            let point = self.public_key();
+            let key = Value::Map(vec![
+                (Value::from(1), Value::from(2)),
+                (Value::from(3), Value::from(COSE_ALG_ES256)),
+                (Value::from(-1), Value::from(1)),
+                (Value::from(-2), Value::Bytes(point[1..33].to_vec())),
+                (Value::from(-3), Value::Bytes(point[33..].to_vec())),
+            ]);
+            let mut encoded = Vec::new();
+            ciborium::ser::into_writer(&key, &mut encoded).unwrap();
+            encoded
+        }
+
+        fn authenticator_data(&self, flags: u8, attested: bool) -> Vec<u8> {
+            let mut data = Sha256::digest(rp_id().as_bytes()).to_vec();
+            data.push(flags);
+            data.extend_from_slice(&self.sign_count.to_be_bytes());
+            if attested {
+                data.extend_from_slice(&[0u8; 16]);
+                data.extend_from_slice(&(CREDENTIAL_ID.len() as u16).to_be_bytes());
+                data.extend_from_slice(CREDENTIAL_ID);
+                data.extend_from_slice(&self.cose_key());
+            }
+            data
+        }
+
+        /// `navigator.credentials.create()` with `none` attestation.
+        fn attestation_object(&self, flags: u8) -> Vec<u8> {
+            let object = Value::Map(vec![
+                (Value::from("fmt"), Value::from("none")),
+                (Value::from("attStmt"), Value::Map(Vec::new())),
+                (Value::from("authData"), Value::Bytes(self.authenticator_data(flags, true))),
+            ]);
+            let mut encoded = Vec::new();
+            ciborium::ser::into_writer(&object, &mut encoded).unwrap();
+            encoded
+        }
+
+        /// `navigator.credentials.get()`: authenticator data and a DER signature.
+        fn assert(&mut self, flags: u8, client_data_json: &[u8]) -> (Vec<u8>, Vec<u8>) {
+            self.sign_count += 1;
+            let authenticator_data = self.authenticator_data(flags, false);
+            let mut signed_data = authenticator_data.clone();
+            signed_data.extend_from_slice(&Sha256::digest(client_data_json));
+            let signature: Signature = self.key.sign(&signed_data);
+            (authenticator_data, signature.to_der().as_bytes().to_vec())
+        }
+    }
+
+    fn client_data(ceremony_type: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "type": ceremony_type,
+            "challenge": "challenge",
+            "origin": rp_origin(),
+        }))
+        .unwrap()
+    }
+
+    const PRESENT_AND_VERIFIED: u8 = FLAG_USER_PRESENT | FLAG_USER_VERIFIED;
+
+    #[test]
+    fn registration_yields_the_credential_and_its_public_key() {
+        let authenticator = SoftwareAuthenticator::new();
+        let attestation = authenticator.attestation_object(PRESENT_AND_VERIFIED | FLAG_ATTESTED_CREDENTIAL_DATA);
+
+        let auth_data = parse_attestation_object(&attestation).unwrap();
+        let credential = parse_authenticator_data(&auth_data).unwrap().attested_credential.unwrap();
+
+        assert_eq!(credential.credential_id, CREDENTIAL_ID);
+        assert_eq!(credential.public_key, authenticator.public_key());
+        assert!(parse_client_data(&client_data("webauthn.create"), "webauthn.create").is_ok());
+        assert!(parse_client_data(&client_data("webauthn.create"), "webauthn.get").is_err());
+    }
+
+    #[test]
+    fn registration_without_user_verification_is_rejected() {
+        let authenticator = SoftwareAuthenticator::new();
+        let attestation = authenticator.attestation_object(FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA);
+
+        let auth_data = parse_attestation_object(&attestation).unwrap();
+        assert!(parse_authenticator_data(&auth_data).is_err());
+    }
+
+    #[test]
+    fn assertion_with_a_valid_signature_verifies() {
+        let mut authenticator = SoftwareAuthenticator::new();
+        let client_data_json = client_data("webauthn.get");
+        let (authenticator_data, signature) = authenticator.assert(PRESENT_AND_VERIFIED, &client_data_json);
+
+        let auth_data = parse_authenticator_data(&authenticator_data).unwrap();
+        assert_eq!(auth_data.sign_count, 1);
+        assert!(verify_assertion_signature(&authenticator.public_key(), &authenticator_data, &client_data_json, &signature).is_ok());
+    }
+
+    #[test]
+    fn assertion_with_a_bad_signature_is_rejected() {
+        let mut authenticator = SoftwareAuthenticator::new();
+        let client_data_json = client_data("webauthn.get");
+        let (authenticator_data, signature) = authenticator.assert(PRESENT_AND_VERIFIED, &client_data_json);
+
+        // Signed over different client data
+        let tampered = client_data("webauthn.create");
+        assert!(verify_assertion_signature(&authenticator.public_key(), &authenticator_data, &tampered, &signature).is_err());
+
+        // Signed by another key
+        let other = SoftwareAuthenticator {
+            key: SigningKey::from_slice(&[9u8; 32]).unwrap(),
+            sign_count: 0,
+        };
+        assert!(verify_assertion_signature(&other.public_key(), &authenticator_data, &client_data_json, &signature).is_err());
+
+        // Not a DER signature at all
+        assert!(verify_assertion_signature(&authenticator.public_key(), &authenticator_data, &client_data_json, &[0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn assertion_without_user_verification_is_rejected() {
+        let mut authenticator = SoftwareAuthenticator::new();
+        let (authenticator_data, _) = authenticator.assert(FLAG_USER_PRESENT, &client_data("webauthn.get"));
+
+        assert!(parse_authenticator_data(&authenticator_data).is_err());
+    }
+
+    #[test]
+    fn sign_count_has_to_move_forward() {
+        assert!(check_sign_count(4, 5).is_ok());
+        assert!(check_sign_count(5, 5).is_err());
+        assert!(check_sign_count(5, 3).is_err());
+        assert!(check_sign_count(5, 0).is_err());
+        // Authenticators without a counter
+        assert!(check_sign_count(0, 0).is_ok());
+    }
+}